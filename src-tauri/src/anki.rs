@@ -0,0 +1,194 @@
+use serde::Serialize;
+use crate::claude::CorrectionEdit;
+use crate::reviews::sentence_containing;
+use crate::storage::DiaryEntry;
+use crate::vocabulary::VocabItem;
+
+// Anki's plain-text import understands these header lines (Anki 2.1.55+), so the
+// file imports with note types, decks and tags already set.
+const TSV_HEADER: &str = "#separator:tab\n#html:true\n#notetype column:1\n#deck column:2\n#tags column:5\n";
+const DECK_ROOT: &str = "Diary Sensei";
+/// Built-in note types, present in every Anki profile.
+const CORRECTION_NOTE_TYPE: &str = "Basic";
+const VOCABULARY_NOTE_TYPE: &str = "Basic (and reversed card)";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnkiExportSummary {
+    pub corrections: usize,
+    pub vocabulary: usize,
+    /// Entries in the range that could not be read and were left out.
+    pub skipped: Vec<String>,
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\n', "<br>")
+        .replace('\t', " ")
+}
+
+/// Quote a field when it contains a double quote, which Anki would otherwise
+/// treat as the start of a quoted field.
+fn tsv_field(field: &str) -> String {
+    if field.contains('"') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn tags(kind: &str, language: &str, date: &str) -> String {
+    let month = date.get(..7).unwrap_or(date);
+    format!("diary-sensei {} lang::{} month::{}", kind, language, month)
+}
+
+fn push_row(out: &mut String, note_type: &str, language: &str, front: &str, back: &str, tags: &str) {
+    let deck = format!("{}::{}", DECK_ROOT, language);
+    let row = [note_type, &deck, front, back, tags]
+        .iter()
+        .map(|f| tsv_field(f))
+        .collect::<Vec<_>>()
+        .join("\t");
+    out.push_str(&row);
+    out.push('\n');
+}
+
+/// One card per original sentence: all fixes inside the sentence are applied on
+/// the back, followed by their explanations.
+fn correction_rows(out: &mut String, entry: &DiaryEntry, language: &str, edits: &[CorrectionEdit]) -> usize {
+    // (original sentence, edits in it), in order of first appearance
+    let mut sentences: Vec<(String, Vec<&CorrectionEdit>)> = vec![];
+    for edit in edits.iter().filter(|e| !e.replacement.is_empty()) {
+        let sentence = sentence_containing(&entry.original, &edit.original)
+            .unwrap_or_else(|| edit.original.clone());
+        match sentences.iter_mut().find(|(s, _)| *s == sentence) {
+            Some((_, in_sentence)) => in_sentence.push(edit),
+            None => sentences.push((sentence, vec![edit])),
+        }
+    }
+
+    let tags = tags("correction", language, &entry.meta.date);
+    for (sentence, in_sentence) in &sentences {
+        let mut corrected = sentence.clone();
+        for edit in in_sentence {
+            corrected = corrected.replacen(&edit.original, &edit.replacement, 1);
+        }
+        let explanations: Vec<String> = in_sentence
+            .iter()
+            .map(|e| format!("{} → {}: {}", escape_html(&e.original), escape_html(&e.replacement), escape_html(&e.reason)))
+            .collect();
+        let back = format!("{}<br><br>{}", escape_html(&corrected), explanations.join("<br>"));
+        push_row(out, CORRECTION_NOTE_TYPE, language, &escape_html(sentence), &back, &tags);
+    }
+    sentences.len()
+}
+
+fn vocabulary_row(out: &mut String, item: &VocabItem) {
+    let front = match &item.reading {
+        Some(reading) => format!("{}<br>({})", escape_html(&item.lemma), escape_html(reading)),
+        None => escape_html(&item.lemma),
+    };
+    let back = match &item.example {
+        Some(example) => format!("{}<br><br>{}", escape_html(&item.meaning), escape_html(example)),
+        None => escape_html(&item.meaning),
+    };
+    // Month of the entry the word came from, else of when it was saved
+    let date = item
+        .source_entry_id
+        .as_deref()
+        .and_then(|id| id.get(..10))
+        .unwrap_or(&item.created_at);
+    push_row(out, VOCABULARY_NOTE_TYPE, &item.language, &front, &back, &tags("vocabulary", &item.language, date));
+}
+
+/// Anki-importable TSV of the corrections saved in `entries` and of `vocabulary`.
+pub fn build_tsv(entries: &[DiaryEntry], vocabulary: &[VocabItem]) -> (String, AnkiExportSummary) {
+    let mut out = String::from(TSV_HEADER);
+    let mut summary = AnkiExportSummary { corrections: 0, vocabulary: 0, skipped: vec![] };
+    for entry in entries {
+        let mut languages: Vec<&String> = entry.edits.keys().collect();
+        languages.sort();
+        for language in languages {
+            summary.corrections += correction_rows(&mut out, entry, language, &entry.edits[language]);
+        }
+    }
+    for item in vocabulary {
+        vocabulary_row(&mut out, item);
+        summary.vocabulary += 1;
+    }
+    (out, summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mistakes::MistakeCategory;
+    use crate::storage::test_support;
+    use std::collections::HashMap;
+
+    fn edit(original: &str, replacement: &str, reason: &str) -> CorrectionEdit {
+        CorrectionEdit {
+            original: original.to_string(),
+            replacement: replacement.to_string(),
+            category: MistakeCategory::Other,
+            reason: reason.to_string(),
+        }
+    }
+
+    fn entry(original: &str, edits: Vec<CorrectionEdit>) -> DiaryEntry {
+        let mut entry = test_support::entry("2026-03-05_100000");
+        entry.meta.languages = vec!["en".to_string()];
+        entry.original = original.to_string();
+        entry.edits = HashMap::from([("en".to_string(), edits)]);
+        entry
+    }
+
+    #[test]
+    fn test_corrections_grouped_by_sentence() {
+        let e = entry(
+            "I goed to park yesterday. It was <fun>.",
+            vec![
+                edit("goed", "went", "irregular past"),
+                edit("to park", "to the park", "article needed"),
+                edit("<fun>", "fun", "stray brackets"),
+                edit("hint only", "", "no fix"),
+            ],
+        );
+        let (tsv, summary) = build_tsv(&[e], &[]);
+        assert_eq!(summary, AnkiExportSummary { corrections: 2, vocabulary: 0, skipped: vec![] });
+        let rows: Vec<&str> = tsv.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(rows.len(), 2);
+        let fields: Vec<&str> = rows[0].split('\t').collect();
+        assert_eq!(fields[0], "Basic");
+        assert_eq!(fields[1], "Diary Sensei::en");
+        assert_eq!(fields[2], "I goed to park yesterday.");
+        assert!(fields[3].starts_with("I went to the park yesterday.<br><br>goed → went: irregular past<br>"));
+        assert_eq!(fields[4], "diary-sensei correction lang::en month::2026-03");
+        assert!(rows[1].contains("It was &lt;fun&gt;."));
+    }
+
+    #[test]
+    fn test_vocabulary_rows_and_quoting() {
+        let item = VocabItem {
+            id: 1,
+            language: "ja".to_string(),
+            lemma: "食べる".to_string(),
+            reading: Some("たべる".to_string()),
+            meaning: "to \"eat\"".to_string(),
+            example: Some("寿司を\t食べた。".to_string()),
+            source_entry_id: Some("2026-02-10_090000".to_string()),
+            tags: vec![],
+            created_at: "2026-03-01T10:00:00".to_string(),
+            updated_at: "2026-03-01T10:00:00".to_string(),
+        };
+        let (tsv, summary) = build_tsv(&[], &[item]);
+        assert_eq!(summary.vocabulary, 1);
+        let row = tsv.lines().last().unwrap();
+        assert_eq!(
+            row,
+            "Basic (and reversed card)\tDiary Sensei::ja\t食べる<br>(たべる)\t\"to \"\"eat\"\"<br><br>寿司を 食べた。\"\tdiary-sensei vocabulary lang::ja month::2026-02"
+        );
+        assert!(tsv.starts_with("#separator:tab\n"));
+    }
+}
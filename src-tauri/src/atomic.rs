@@ -0,0 +1,73 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// `name.ext` → `name.ext.<suffix>` in the same directory.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Where `write_file` keeps the previous version of `path`.
+pub fn backup_path(path: &Path) -> PathBuf {
+    sibling(path, "bak")
+}
+
+/// Replace `path` with `data` so that a crash or a full disk leaves either the old
+/// or the new contents, never a truncated file: the data goes to a temp file in the
+/// same directory, is flushed to disk, and is then renamed over `path`. With
+/// `backup`, the previous contents are first copied to `path.bak`.
+pub fn write_file(path: &Path, data: &[u8], backup: bool) -> Result<(), String> {
+    let tmp = sibling(path, "tmp");
+    let result = (|| {
+        let mut file = File::create(&tmp)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        drop(file);
+        if backup && path.exists() {
+            fs::copy(path, backup_path(path))?;
+        }
+        fs::rename(&tmp, path)?;
+        // Make the rename itself durable
+        #[cfg(unix)]
+        if let Some(dir) = path.parent() {
+            File::open(dir)?.sync_all()?;
+        }
+        Ok::<(), std::io::Error>(())
+    })();
+    result.map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        format!("Failed to write {}: {}", path.display(), e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::temp_dir;
+
+    #[test]
+    fn test_write_file_replaces_and_backs_up() {
+        let dir = temp_dir("atomic");
+        let path = dir.join("entry.md");
+
+        write_file(&path, b"first", true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+        assert!(!backup_path(&path).exists());
+
+        write_file(&path, b"second", true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), "first");
+
+        write_file(&path, b"third", false).unwrap();
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), "first");
+        assert!(!sibling(&path, "tmp").exists());
+
+        // A failed write leaves the old file alone and no temp file behind
+        assert!(write_file(&dir.join("missing").join("x.md"), b"x", true).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "third");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use crate::config::config_path;
+use crate::entry_id::EntryId;
+use crate::provider::{AiError, AiProvider, AiTask};
+use crate::storage;
+
+/// Append-only log of diary text sent off this machine, one JSON object per line,
+/// beside config.json. It records what was sent where, never the text itself.
+const AUDIT_FILE: &str = "cloud-audit.jsonl";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub sent_at: String, // ISO 8601
+    /// None for text that does not belong to a saved entry.
+    pub entry_id: Option<String>,
+    pub task: AiTask,
+    pub provider: String,
+    /// Characters of diary text sent, summed over all requests (one per target
+    /// language for translations). Prompts are not counted.
+    pub chars: usize,
+}
+
+fn audit_path() -> PathBuf {
+    config_path().with_file_name(AUDIT_FILE)
+}
+
+fn append_in(path: &Path, record: &AuditRecord) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+    line.push('\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(path).map_err(|e| e.to_string())?;
+    file.write_all(line.as_bytes()).map_err(|e| e.to_string())?;
+    file.sync_data().map_err(|e| e.to_string())
+}
+
+/// The newest `limit` records, newest first. Lines that do not parse are skipped.
+fn list_in(path: &Path, limit: usize) -> Result<Vec<AuditRecord>, String> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    Ok(content
+        .lines()
+        .rev()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(record) => Some(record),
+            Err(e) => {
+                log::warn!("Skipping unreadable audit log line: {}", e);
+                None
+            }
+        })
+        .take(limit)
+        .collect())
+}
+
+fn before_send_in(
+    path: &Path,
+    ai: &dyn AiProvider,
+    entry_id: Option<&EntryId>,
+    task: AiTask,
+    chars: usize,
+) -> Result<(), AiError> {
+    if !ai.capabilities().remote {
+        return Ok(());
+    }
+    if let Some(id) = entry_id {
+        // An entry that cannot be read might be private, so it is not sent either
+        let private = storage::is_entry_private(id).map_err(AiError::Refused)?;
+        if private {
+            return Err(AiError::Refused(format!(
+                "This entry is marked private and cannot be sent to {}. Switch to a local provider to use AI on it.",
+                ai.id()
+            )));
+        }
+    }
+    let record = AuditRecord {
+        sent_at: chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string(),
+        entry_id: entry_id.map(|id| id.to_string()),
+        task,
+        provider: ai.id().to_string(),
+        chars,
+    };
+    append_in(path, &record).map_err(|e| AiError::Refused(format!("Cannot write the audit log: {}", e)))
+}
+
+// --- Public API ---
+
+/// Gate for text about to be sent by `ai`. Local providers pass untouched. A remote
+/// one does not get a saved entry marked `private: true`; an unsaved draft has no
+/// flag on disk, so the frontend keeps private drafts back itself. Every remote
+/// send is logged first, and if the log cannot be written, nothing is sent.
+pub fn before_send(ai: &dyn AiProvider, entry_id: Option<&EntryId>, task: AiTask, chars: usize) -> Result<(), AiError> {
+    before_send_in(&audit_path(), ai, entry_id, task, chars)
+}
+
+/// The newest `limit` records, newest first.
+pub fn list(limit: usize) -> Result<Vec<AuditRecord>, String> {
+    list_in(&audit_path(), limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::temp_dir;
+    use crate::provider::{ChatMessage, ChatOptions, ProviderCapabilities};
+    use futures::future::BoxFuture;
+
+    fn record(entry_id: &str, chars: usize) -> AuditRecord {
+        AuditRecord {
+            sent_at: "2026-07-01T10:00:00".to_string(),
+            entry_id: Some(entry_id.to_string()),
+            task: AiTask::Translation,
+            provider: "claude".to_string(),
+            chars,
+        }
+    }
+
+    struct StubProvider {
+        remote: bool,
+    }
+
+    impl AiProvider for StubProvider {
+        fn id(&self) -> &'static str {
+            "stub"
+        }
+
+        fn capabilities(&self) -> ProviderCapabilities {
+            ProviderCapabilities { remote: self.remote, requires_api_key: false, streaming: false, structured_output: false }
+        }
+
+        fn chat<'a>(
+            &'a self,
+            _messages: &'a [ChatMessage],
+            _options: ChatOptions<'a>,
+        ) -> BoxFuture<'a, Result<String, AiError>> {
+            Box::pin(async { Ok(String::new()) })
+        }
+    }
+
+    #[test]
+    fn test_unsaved_text_is_logged_without_entry_id() {
+        let dir = temp_dir("audit-unsaved");
+        let path = dir.join(AUDIT_FILE);
+        before_send_in(&path, &StubProvider { remote: false }, None, AiTask::Correction, 10).unwrap();
+        assert!(!path.exists());
+
+        before_send_in(&path, &StubProvider { remote: true }, None, AiTask::Correction, 10).unwrap();
+        let records = list_in(&path, 10).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!((records[0].entry_id.as_deref(), records[0].chars), (None, 10));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_append_and_list() {
+        let dir = temp_dir("audit");
+        let path = dir.join(AUDIT_FILE);
+        assert!(list_in(&path, 10).unwrap().is_empty());
+
+        append_in(&path, &record("2026-07-01_100000", 120)).unwrap();
+        append_in(&path, &record("2026-07-01_110000", 80)).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{ torn line\n").unwrap();
+        append_in(&path, &record("2026-07-01_120000", 40)).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("{\"sent_at\":\"2026-07-01T10:00:00\",\"entry_id\":\"2026-07-01_100000\",\"task\":\"translation\""));
+        let records = list_in(&path, 10).unwrap();
+        let chars: Vec<usize> = records.iter().map(|r| r.chars).collect();
+        assert_eq!(chars, vec![40, 80, 120]);
+        assert_eq!(list_in(&path, 1).unwrap(), vec![record("2026-07-01_120000", 40)]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,27 +1,69 @@
-use reqwest::Client;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use crate::config::CorrectionStrength;
+use crate::mistakes::MistakeCategory;
+use crate::provider::{AiError, ChatMessage, GenerationOptions, MAX_RETRY_DELAY};
+
+/// Receives each piece of text as it arrives from a streaming response.
+pub type OnDelta<'a> = &'a (dyn Fn(&str) + Send + Sync);
 
 // --- Shared prompt builders ---
 
-pub fn correction_prompt(language: &str, explanation_language: &str) -> String {
-    format!(
-        "You are a {0} language teacher. The student wrote a diary that may contain text in multiple languages.\n\
-        CORRECT only the {0} portions. Do NOT translate to another language.\n\n\
-        Rules:\n\
-        - Fix grammar, unnatural expressions, and word choice in {0} text only.\n\
-        - Preserve ALL text in other languages exactly as-is (do not remove or modify it).\n\
-        - Keep the student's writing style and tone.\n\
-        - Keep line breaks, emoji, and image references unchanged.\n\
-        - If the {0} text is already perfect, return everything unchanged.\n\n\
-        Format your response EXACTLY like this:\n\
-        [CORRECTED]\n\
-        (the COMPLETE text with {0} portions corrected — preserve non-{0} text unchanged)\n\
-        [EXPLANATION]\n\
-        (numbered list in {1}: \"original\" → \"corrected\" — reason.\n\
-        If no corrections needed, write: No corrections needed.)\n\n\
-        IMPORTANT: Return the COMPLETE input text. Only modify {0} portions.",
-        language, explanation_language
-    )
+pub fn correction_prompt(
+    language: &str,
+    explanation_language: &str,
+    strength: CorrectionStrength,
+    categories: &[MistakeCategory],
+) -> String {
+    let categories = format!(
+        "one of: {}",
+        categories.iter().map(|c| c.as_str()).collect::<Vec<_>>().join(", ")
+    );
+    match strength {
+        CorrectionStrength::Hint => format!(
+            "You are a {0} language teacher. The student wrote a diary that may contain text in multiple languages.\n\
+            The student wants to fix their own mistakes, so DO NOT correct anything. Only point out where the problems are.\n\n\
+            Rules:\n\
+            - Find grammar mistakes, wrong word choice, and spelling errors in the {0} text only.\n\
+            - Do NOT give the corrected wording. Describe what kind of problem it is so the student can fix it.\n\
+            - Ignore text in other languages.\n\n\
+            Respond with ONLY a JSON object of this shape:\n\
+            {{\"corrected\": \"(the COMPLETE input text, unchanged)\",\n\
+            \"edits\": [{{\"original\": \"(exact problem span copied from the input)\", \"replacement\": \"\", \
+            \"category\": \"({2})\", \"reason\": \"(hint in {1} about what is wrong)\"}}]}}\n\
+            If there are no problems, return an empty \"edits\" list.\n\n\
+            IMPORTANT: Return the input text exactly as written. Never reveal the corrected form.",
+            language, explanation_language, categories
+        ),
+        CorrectionStrength::Standard | CorrectionStrength::Full => {
+            let scope = if strength == CorrectionStrength::Full {
+                "- Fix grammar, spelling and word choice, AND rewrite awkward or unnatural phrasing so it reads like a native speaker wrote it.\n\
+                - Keep the meaning and the student's tone."
+            } else {
+                "- Fix grammar, spelling, and clearly wrong word choice only.\n\
+                - Do NOT rephrase sentences that are already correct, even if a native speaker might say them differently.\n\
+                - Keep the student's writing style and tone."
+            };
+            format!(
+                "You are a {0} language teacher. The student wrote a diary that may contain text in multiple languages.\n\
+                CORRECT only the {0} portions. Do NOT translate to another language.\n\n\
+                Rules:\n\
+                {2}\n\
+                - Preserve ALL text in other languages exactly as-is (do not remove or modify it).\n\
+                - Keep line breaks, emoji, and image references unchanged.\n\
+                - If the {0} text is already perfect, return everything unchanged.\n\n\
+                Respond with ONLY a JSON object of this shape:\n\
+                {{\"corrected\": \"(the COMPLETE text with {0} portions corrected — preserve non-{0} text unchanged)\",\n\
+                \"edits\": [{{\"original\": \"(exact span copied from the input)\", \"replacement\": \"(corrected span)\", \
+                \"category\": \"({3})\", \"reason\": \"(short explanation in {1})\"}}]}}\n\
+                List every change you made as one edit. If no corrections are needed, return an empty \"edits\" list.\n\n\
+                IMPORTANT: \"corrected\" must contain the COMPLETE input text. Only modify {0} portions.",
+                language, explanation_language, scope, categories
+            )
+        }
+    }
 }
 
 pub fn translation_prompt(target_language: &str) -> String {
@@ -39,25 +81,397 @@ pub fn translation_prompt(target_language: &str) -> String {
     )
 }
 
-// --- Claude API ---
+// --- Structured correction output ---
 
-#[derive(Serialize)]
-struct ClaudeMessage {
-    role: String,
-    content: String,
+/// One change the model made (or, in hint mode, one problem it found).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CorrectionEdit {
+    /// Span as it appears in the student's text.
+    pub original: String,
+    /// Corrected span; empty in hint mode.
+    #[serde(default)]
+    pub replacement: String,
+    #[serde(default)]
+    pub category: MistakeCategory,
+    #[serde(default)]
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CorrectionOutput {
+    pub corrected: String,
+    #[serde(default)]
+    pub edits: Vec<CorrectionEdit>,
+}
+
+/// JSON schema the correction reply must match, restricted to `categories`.
+pub fn correction_schema(categories: &[MistakeCategory]) -> serde_json::Value {
+    let categories: Vec<&str> = categories.iter().map(|c| c.as_str()).collect();
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "corrected": { "type": "string" },
+            "edits": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "original": { "type": "string" },
+                        "replacement": { "type": "string" },
+                        "category": { "type": "string", "enum": categories },
+                        "reason": { "type": "string" }
+                    },
+                    "required": ["original", "replacement", "category", "reason"]
+                }
+            }
+        },
+        "required": ["corrected", "edits"]
+    })
+}
+
+/// The JSON object in a reply, tolerating Markdown code fences or chatter around it.
+fn extract_json_object(raw: &str) -> Option<&str> {
+    let start = raw.find('{')?;
+    let end = raw.rfind('}')?;
+    (end > start).then(|| &raw[start..=end])
+}
+
+/// Parse and validate a correction reply against `correction_schema`.
+pub fn parse_correction_output(raw: &str) -> Result<CorrectionOutput, AiError> {
+    let json = extract_json_object(raw).ok_or_else(|| {
+        AiError::MalformedOutput("The AI response did not contain a JSON correction.".to_string())
+    })?;
+    let mut output: CorrectionOutput = serde_json::from_str(json)
+        .map_err(|e| AiError::MalformedOutput(format!("The AI correction did not match the expected format: {}", e)))?;
+    if output.corrected.trim().is_empty() {
+        return Err(AiError::MalformedOutput("The AI correction was empty.".to_string()));
+    }
+    output.edits.retain(|edit| !edit.original.trim().is_empty());
+    Ok(output)
+}
+
+/// Numbered, human-readable list of edits, in the same shape the old
+/// `[EXPLANATION]` section used. None when nothing was changed.
+pub fn explanation_from_edits(edits: &[CorrectionEdit]) -> Option<String> {
+    if edits.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = edits
+        .iter()
+        .enumerate()
+        .map(|(i, edit)| {
+            if edit.replacement.is_empty() {
+                format!("{}. \"{}\" — {}", i + 1, edit.original, edit.reason)
+            } else {
+                format!("{}. \"{}\" → \"{}\" — {}", i + 1, edit.original, edit.replacement, edit.reason)
+            }
+        })
+        .collect();
+    Some(lines.join("\n"))
+}
+
+/// Pulls one string field out of a JSON reply while it is still streaming, so the
+/// user sees the corrected text take shape instead of raw JSON fragments.
+pub struct FieldStream {
+    key: String,
+    raw: String,
+    /// Position in `raw` up to which the field's value has been decoded.
+    pos: Option<usize>,
+    done: bool,
+}
+
+impl FieldStream {
+    pub fn new(field: &str) -> Self {
+        FieldStream { key: format!("\"{}\"", field), raw: String::new(), pos: None, done: false }
+    }
+
+    /// Position just after the opening quote of the field's value, once it has arrived.
+    fn value_start(&self) -> Option<usize> {
+        let after_key = self.raw.find(&self.key)? + self.key.len();
+        let rest = self.raw[after_key..].trim_start().strip_prefix(':')?.trim_start();
+        rest.starts_with('"').then(|| self.raw.len() - rest.len() + 1)
+    }
+
+    /// Add the next fragment of the reply. Returns the newly decoded part of the
+    /// field, which is empty until the value starts and after it ends.
+    pub fn push(&mut self, fragment: &str) -> String {
+        if self.done {
+            return String::new();
+        }
+        self.raw.push_str(fragment);
+        if self.pos.is_none() {
+            self.pos = self.value_start();
+        }
+        let Some(mut pos) = self.pos else { return String::new() };
+
+        let mut out = String::new();
+        while let Some(c) = self.raw[pos..].chars().next() {
+            match c {
+                '"' => {
+                    self.done = true;
+                    break;
+                }
+                '\\' => {
+                    // An escape split across fragments waits for the rest to arrive
+                    let Some((text, len)) = decode_escape(&self.raw[pos..]) else { break };
+                    out.push_str(&text);
+                    pos += len;
+                }
+                c => {
+                    out.push(c);
+                    pos += c.len_utf8();
+                }
+            }
+        }
+        self.pos = Some(pos);
+        out
+    }
+}
+
+/// Decode the JSON escape at the start of `s`, returning the text and the bytes it
+/// took, or None when `s` ends before the escape does.
+fn decode_escape(s: &str) -> Option<(String, usize)> {
+    let hex = |at: usize| s.get(at..at + 4).and_then(|h| u16::from_str_radix(h, 16).ok());
+    let c = s[1..].chars().next()?;
+    let decoded = match c {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        'b' => '\u{8}',
+        'f' => '\u{c}',
+        'u' => {
+            let unit = hex(2)?;
+            if !(0xD800..0xDC00).contains(&unit) {
+                return Some((char::from_u32(unit as u32).unwrap_or('\u{FFFD}').to_string(), 6));
+            }
+            // A high surrogate needs the `\uXXXX` of its low half too
+            if s.len() < 12 {
+                return None;
+            }
+            let low = if s.get(6..8) == Some("\\u") { hex(8).unwrap_or(0) } else { 0 };
+            let text = char::decode_utf16([unit, low]).map(|r| r.unwrap_or('\u{FFFD}')).collect();
+            return Some((text, 12));
+        }
+        c => c,
+    };
+    Some((decoded.to_string(), 1 + c.len_utf8()))
+}
+
+// --- Vocabulary extraction ---
+
+pub fn vocabulary_prompt(language: &str, explanation_language: &str) -> String {
+    format!(
+        "You help a student build a {0} vocabulary notebook from their own diary.\n\
+        You will get a {0} text the student wrote or had translated, and possibly the corrections they received.\n\n\
+        Rules:\n\
+        - Pick words and expressions worth studying: useful vocabulary, idioms, and every corrected expression.\n\
+        - Skip trivial words (basic pronouns, numbers, names).\n\
+        - Give each word in its dictionary form.\n\
+        - Add a reading only where the script needs one (e.g. kana for kanji, pinyin for hanzi); otherwise leave it empty.\n\
+        - The example must be a sentence copied from the text.\n\n\
+        Respond with ONLY a JSON object of this shape:\n\
+        {{\"items\": [{{\"lemma\": \"(dictionary form)\", \"reading\": \"(reading or empty)\", \
+        \"meaning\": \"(short meaning in {1})\", \"example\": \"(sentence from the text)\"}}]}}",
+        language, explanation_language
+    )
+}
+
+/// A word the model suggests adding to the vocabulary notebook.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct VocabCandidate {
+    pub lemma: String,
+    #[serde(default)]
+    pub reading: String,
+    #[serde(default)]
+    pub meaning: String,
+    #[serde(default)]
+    pub example: String,
+}
+
+#[derive(Deserialize)]
+struct VocabularyOutput {
+    #[serde(default)]
+    items: Vec<VocabCandidate>,
+}
+
+pub fn vocabulary_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "items": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "lemma": { "type": "string" },
+                        "reading": { "type": "string" },
+                        "meaning": { "type": "string" },
+                        "example": { "type": "string" }
+                    },
+                    "required": ["lemma", "reading", "meaning", "example"]
+                }
+            }
+        },
+        "required": ["items"]
+    })
+}
+
+/// Parse a reply matching `vocabulary_schema`, dropping items without a lemma.
+pub fn parse_vocabulary_output(raw: &str) -> Result<Vec<VocabCandidate>, AiError> {
+    let json = extract_json_object(raw).ok_or_else(|| {
+        AiError::MalformedOutput("The AI response did not contain a JSON word list.".to_string())
+    })?;
+    let output: VocabularyOutput = serde_json::from_str(json)
+        .map_err(|e| AiError::MalformedOutput(format!("The AI word list did not match the expected format: {}", e)))?;
+    Ok(output
+        .items
+        .into_iter()
+        .filter(|item| !item.lemma.trim().is_empty())
+        .collect())
+}
+
+// --- Error classification ---
+
+/// Seconds from a `retry-after` header (the HTTP-date form is not used by AI providers),
+/// capped at `MAX_RETRY_DELAY`. Negative, infinite or unparsable values are ignored.
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?;
+    let secs: f64 = value.trim().parse().ok()?;
+    if !secs.is_finite() || secs < 0.0 {
+        return None;
+    }
+    Some(Duration::try_from_secs_f64(secs).map_or(MAX_RETRY_DELAY, |d| d.min(MAX_RETRY_DELAY)))
+}
+
+/// The human-readable part of an error body: `error.message`, a bare `error` string, or the raw body.
+fn error_detail(body: &str) -> String {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(body) else {
+        return body.trim().to_string();
+    };
+    json["error"]["message"]
+        .as_str()
+        .or_else(|| json["error"].as_str())
+        .map(String::from)
+        .unwrap_or_else(|| body.trim().to_string())
+}
+
+/// Map a non-2xx response to an `AiError`. `service` prefixes the message, e.g. "Claude API".
+fn error_for_status(service: &str, status: StatusCode, headers: &HeaderMap, body: &str) -> AiError {
+    let message = format!("{} error {}: {}", service, status, error_detail(body));
+    let retry_after = parse_retry_after(headers);
+    match status.as_u16() {
+        401 | 403 => AiError::Auth(message),
+        404 => AiError::ModelNotFound(message),
+        408 | 504 => AiError::Timeout(message),
+        429 => AiError::RateLimited { message, retry_after },
+        503 | 529 => AiError::Overloaded { message, retry_after },
+        400..=499 => AiError::BadRequest(message),
+        _ => AiError::Server(message),
+    }
 }
 
+async fn error_from_response(service: &str, response: reqwest::Response) -> AiError {
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response.text().await.unwrap_or_default();
+    error_for_status(service, status, &headers, &body)
+}
+
+/// Map a transport failure. `unreachable` is the message shown when nothing is listening.
+fn error_for_request(e: reqwest::Error, unreachable: &str, timed_out: &str) -> AiError {
+    if e.is_connect() {
+        AiError::ConnectionRefused(unreachable.to_string())
+    } else if e.is_timeout() {
+        AiError::Timeout(timed_out.to_string())
+    } else {
+        AiError::Network(format!("HTTP error: {}", e))
+    }
+}
+
+fn malformed(e: impl std::fmt::Display) -> AiError {
+    AiError::MalformedOutput(format!("Parse error: {}", e))
+}
+
+fn build_client(timeout: Duration) -> Result<Client, AiError> {
+    Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| AiError::NotConfigured(format!("Client error: {}", e)))
+}
+
+// --- Streaming helpers ---
+
+/// Read a streaming response body line by line, handing each non-empty line to `on_line`.
+/// Bytes are buffered until a newline so multi-byte characters split across chunks stay intact.
+async fn read_lines(
+    mut response: reqwest::Response,
+    mut on_line: impl FnMut(&str) -> Result<(), AiError>,
+) -> Result<(), AiError> {
+    let mut buf: Vec<u8> = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| {
+            if e.is_timeout() {
+                AiError::Timeout(format!("Stream timed out: {}", e))
+            } else {
+                AiError::Network(format!("Stream error: {}", e))
+            }
+        })?
+    {
+        buf.extend_from_slice(&chunk);
+        while let Some(pos) = buf.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buf.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            if !line.trim().is_empty() {
+                on_line(line.trim())?;
+            }
+        }
+    }
+    let rest = String::from_utf8_lossy(&buf);
+    if !rest.trim().is_empty() {
+        on_line(rest.trim())?;
+    }
+    Ok(())
+}
+
+/// Payload of an SSE `data:` line, or None for `event:`/comment lines.
+fn sse_data(line: &str) -> Option<&str> {
+    line.strip_prefix("data:").map(|d| d.trim())
+}
+
+// --- Claude API ---
+
 #[derive(Serialize)]
 struct ClaudeRequest {
     model: String,
     max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
     system: String,
-    messages: Vec<ClaudeMessage>,
+    messages: Vec<ChatMessage>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<ClaudeTool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<serde_json::Value>,
+}
+
+/// Structured output is requested by forcing Claude to call this tool.
+const CLAUDE_STRUCTURED_TOOL: &str = "structured_response";
+
+#[derive(Serialize)]
+struct ClaudeTool {
+    name: String,
+    description: String,
+    input_schema: serde_json::Value,
 }
 
 #[derive(Deserialize)]
 struct ClaudeContentBlock {
     text: Option<String>,
+    /// Arguments of a `tool_use` block.
+    input: Option<serde_json::Value>,
 }
 
 #[derive(Deserialize)]
@@ -65,17 +479,69 @@ struct ClaudeResponse {
     content: Vec<ClaudeContentBlock>,
 }
 
-pub async fn call_claude(api_key: &str, system: &str, user_text: &str) -> Result<String, String> {
-    let client = Client::new();
+/// Text carried by one Claude SSE line (`content_block_delta` events), if any.
+/// For tool use this is a fragment of the tool's JSON input.
+fn parse_claude_stream_line(line: &str) -> Result<Option<String>, AiError> {
+    let Some(data) = sse_data(line) else {
+        return Ok(None);
+    };
+    let event: serde_json::Value = serde_json::from_str(data).map_err(malformed)?;
+    match event["type"].as_str() {
+        Some("content_block_delta") => Ok(event["delta"]["text"]
+            .as_str()
+            .or_else(|| event["delta"]["partial_json"].as_str())
+            .map(String::from)),
+        Some("error") => {
+            let message = format!(
+                "Claude API error: {}",
+                event["error"]["message"].as_str().unwrap_or("unknown error")
+            );
+            Err(match event["error"]["type"].as_str() {
+                Some("overloaded_error") => AiError::Overloaded { message, retry_after: None },
+                Some("rate_limit_error") => AiError::RateLimited { message, retry_after: None },
+                Some("authentication_error") | Some("permission_error") => AiError::Auth(message),
+                Some("not_found_error") => AiError::ModelNotFound(message),
+                Some("invalid_request_error") => AiError::BadRequest(message),
+                _ => AiError::Server(message),
+            })
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Call the Anthropic Messages API. With `on_delta` the response is streamed and
+/// each text fragment is forwarded as it arrives; the full text is returned either way.
+/// With `json_schema` Claude is forced to answer through a tool whose input is that
+/// schema, and the tool input is returned as JSON text.
+pub async fn call_claude(
+    api_key: &str,
+    model: &str,
+    system: &str,
+    messages: &[ChatMessage],
+    options: &GenerationOptions,
+    json_schema: Option<&serde_json::Value>,
+    on_delta: Option<OnDelta<'_>>,
+) -> Result<String, AiError> {
+    let client = build_client(options.timeout)?;
 
     let request = ClaudeRequest {
-        model: "claude-haiku-4-5-20251001".to_string(),
-        max_tokens: 4096,
+        model: model.to_string(),
+        max_tokens: options.max_tokens,
+        temperature: options.temperature,
         system: system.to_string(),
-        messages: vec![ClaudeMessage {
-            role: "user".to_string(),
-            content: user_text.to_string(),
-        }],
+        messages: messages.to_vec(),
+        stream: on_delta.is_some(),
+        tools: json_schema
+            .map(|schema| {
+                vec![ClaudeTool {
+                    name: CLAUDE_STRUCTURED_TOOL.to_string(),
+                    description: "Return the response in the required structure.".to_string(),
+                    input_schema: schema.clone(),
+                }]
+            })
+            .unwrap_or_default(),
+        tool_choice: json_schema
+            .map(|_| serde_json::json!({ "type": "tool", "name": CLAUDE_STRUCTURED_TOOL })),
     };
 
     let response = client
@@ -86,39 +552,61 @@ pub async fn call_claude(api_key: &str, system: &str, user_text: &str) -> Result
         .json(&request)
         .send()
         .await
-        .map_err(|e| format!("HTTP error: {}", e))?;
+        .map_err(|e| {
+            error_for_request(e, "Cannot connect to the Claude API.", "Claude API request timed out.")
+        })?;
 
     if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        return Err(format!("Claude API error {}: {}", status, body));
+        return Err(error_from_response("Claude API", response).await);
     }
 
-    let api_response: ClaudeResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Parse error: {}", e))?;
+    if let Some(on_delta) = on_delta {
+        let mut full = String::new();
+        read_lines(response, |line| {
+            if let Some(text) = parse_claude_stream_line(line)? {
+                on_delta(&text);
+                full.push_str(&text);
+            }
+            Ok(())
+        })
+        .await?;
+        if full.is_empty() {
+            return Err(AiError::MalformedOutput("Empty response from Claude".to_string()));
+        }
+        return Ok(full);
+    }
 
-    api_response
-        .content
-        .first()
-        .and_then(|block| block.text.clone())
-        .ok_or_else(|| "Empty response from Claude".to_string())
+    let api_response: ClaudeResponse = response.json().await.map_err(malformed)?;
+
+    let text = if json_schema.is_some() {
+        api_response
+            .content
+            .iter()
+            .find_map(|block| block.input.as_ref())
+            .map(|input| input.to_string())
+    } else {
+        api_response.content.first().and_then(|block| block.text.clone())
+    };
+    text.ok_or_else(|| AiError::MalformedOutput("Empty response from Claude".to_string()))
 }
 
 // --- Ollama API ---
 
 #[derive(Serialize)]
-struct OllamaMessage {
-    role: String,
-    content: String,
+struct OllamaOptions {
+    num_predict: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
 }
 
 #[derive(Serialize)]
 struct OllamaRequest {
     model: String,
-    messages: Vec<OllamaMessage>,
+    messages: Vec<ChatMessage>,
     stream: bool,
+    options: OllamaOptions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'static str>,
 }
 
 #[derive(Deserialize)]
@@ -132,30 +620,46 @@ struct OllamaResponse {
     error: Option<String>,
 }
 
+/// Text carried by one line of Ollama's newline-delimited JSON stream.
+/// Ollama reports some failures (e.g. a missing model mid-stream) inside a 200 body.
+fn ollama_body_error(err: String) -> AiError {
+    let message = format!("Ollama error: {}", err);
+    if err.contains("not found") {
+        AiError::ModelNotFound(message)
+    } else {
+        AiError::Server(message)
+    }
+}
+
+fn parse_ollama_stream_line(line: &str) -> Result<Option<String>, AiError> {
+    let chunk: OllamaResponse = serde_json::from_str(line).map_err(malformed)?;
+    if let Some(err) = chunk.error {
+        return Err(ollama_body_error(err));
+    }
+    Ok(chunk.message.and_then(|m| m.content).filter(|c| !c.is_empty()))
+}
+
+/// Call Ollama's `/api/chat`, streaming fragments to `on_delta` when given.
+/// `json` switches on `format: "json"`; the expected shape is described in the prompt.
 pub async fn call_ollama(
     base_url: &str,
     model: &str,
-    system: &str,
-    user_text: &str,
-) -> Result<String, String> {
-    let client = Client::builder()
-        .timeout(std::time::Duration::from_secs(120))
-        .build()
-        .map_err(|e| format!("Client error: {}", e))?;
+    messages: &[ChatMessage],
+    options: &GenerationOptions,
+    json: bool,
+    on_delta: Option<OnDelta<'_>>,
+) -> Result<String, AiError> {
+    let client = build_client(options.timeout)?;
 
     let request = OllamaRequest {
         model: model.to_string(),
-        messages: vec![
-            OllamaMessage {
-                role: "system".to_string(),
-                content: system.to_string(),
-            },
-            OllamaMessage {
-                role: "user".to_string(),
-                content: user_text.to_string(),
-            },
-        ],
-        stream: false,
+        messages: messages.to_vec(),
+        stream: on_delta.is_some(),
+        options: OllamaOptions {
+            num_predict: options.max_tokens,
+            temperature: options.temperature,
+        },
+        format: json.then_some("json"),
     };
 
     let url = format!("{}/api/chat", base_url.trim_end_matches('/'));
@@ -167,32 +671,512 @@ pub async fn call_ollama(
         .send()
         .await
         .map_err(|e| {
-            if e.is_connect() {
-                "Cannot connect to Ollama. Make sure it's running: ollama serve".to_string()
-            } else if e.is_timeout() {
-                "Ollama request timed out. The model might be loading for the first time.".to_string()
-            } else {
-                format!("HTTP error: {}", e)
-            }
+            error_for_request(
+                e,
+                OLLAMA_UNREACHABLE,
+                "Ollama request timed out. The model might be loading for the first time.",
+            )
         })?;
 
     if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        return Err(format!("Ollama error {}: {}", status, body));
+        return Err(error_from_response("Ollama", response).await);
     }
 
-    let ollama_response: OllamaResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Parse error: {}", e))?;
+    if let Some(on_delta) = on_delta {
+        let mut full = String::new();
+        read_lines(response, |line| {
+            if let Some(text) = parse_ollama_stream_line(line)? {
+                on_delta(&text);
+                full.push_str(&text);
+            }
+            Ok(())
+        })
+        .await?;
+        if full.is_empty() {
+            return Err(AiError::MalformedOutput("Empty response from Ollama".to_string()));
+        }
+        return Ok(full);
+    }
+
+    let ollama_response: OllamaResponse = response.json().await.map_err(malformed)?;
 
     if let Some(err) = ollama_response.error {
-        return Err(format!("Ollama error: {}", err));
+        return Err(ollama_body_error(err));
     }
 
     ollama_response
         .message
         .and_then(|m| m.content)
-        .ok_or_else(|| "Empty response from Ollama".to_string())
+        .ok_or_else(|| AiError::MalformedOutput("Empty response from Ollama".to_string()))
+}
+
+// --- Ollama model management ---
+
+const OLLAMA_UNREACHABLE: &str = "Cannot connect to Ollama. Make sure it's running: ollama serve";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OllamaModelDetails {
+    #[serde(default)]
+    pub family: Option<String>,
+    #[serde(default)]
+    pub parameter_size: Option<String>,
+    #[serde(default)]
+    pub quantization_level: Option<String>,
+}
+
+/// One installed model as reported by `/api/tags`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaModel {
+    pub name: String,
+    #[serde(default)]
+    pub size: u64, // bytes on disk
+    #[serde(default)]
+    pub modified_at: Option<String>,
+    #[serde(default)]
+    pub details: OllamaModelDetails,
+}
+
+#[derive(Deserialize)]
+struct OllamaTagsResponse {
+    #[serde(default)]
+    models: Vec<OllamaModel>,
+}
+
+#[derive(Deserialize)]
+struct OllamaVersionResponse {
+    version: String,
+}
+
+/// One line of `/api/pull` progress. `total`/`completed` are only present while downloading layers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaPullProgress {
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub digest: Option<String>,
+    #[serde(default)]
+    pub total: Option<u64>,
+    #[serde(default)]
+    pub completed: Option<u64>,
+    #[serde(default, skip_serializing)]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct OllamaPullRequest {
+    model: String,
+    stream: bool,
+}
+
+/// Whether `configured` (as typed in Settings) names the installed model `installed`.
+/// Ollama treats a name without a tag as `:latest`.
+pub fn ollama_model_matches(configured: &str, installed: &str) -> bool {
+    fn with_tag(name: &str) -> String {
+        let name = name.trim();
+        if name.contains(':') {
+            name.to_string()
+        } else {
+            format!("{}:latest", name)
+        }
+    }
+    with_tag(configured) == with_tag(installed)
+}
+
+async fn ollama_get(base_url: &str, path: &str) -> Result<reqwest::Response, AiError> {
+    let client = build_client(Duration::from_secs(5))?;
+    let url = format!("{}{}", base_url.trim_end_matches('/'), path);
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| error_for_request(e, OLLAMA_UNREACHABLE, "Ollama did not respond in time."))?;
+    if !response.status().is_success() {
+        return Err(error_from_response("Ollama", response).await);
+    }
+    Ok(response)
+}
+
+pub async fn ollama_version(base_url: &str) -> Result<String, AiError> {
+    let response = ollama_get(base_url, "/api/version").await?;
+    let version: OllamaVersionResponse = response.json().await.map_err(malformed)?;
+    Ok(version.version)
+}
+
+pub async fn ollama_list_models(base_url: &str) -> Result<Vec<OllamaModel>, AiError> {
+    let response = ollama_get(base_url, "/api/tags").await?;
+    let tags: OllamaTagsResponse = response.json().await.map_err(malformed)?;
+    Ok(tags.models)
+}
+
+/// Download `model`, forwarding each progress line to `on_progress` until Ollama reports success.
+pub async fn ollama_pull(
+    base_url: &str,
+    model: &str,
+    on_progress: &(dyn Fn(&OllamaPullProgress) + Send + Sync),
+) -> Result<(), AiError> {
+    // No overall timeout: large models take many minutes to download.
+    let client = Client::new();
+    let url = format!("{}/api/pull", base_url.trim_end_matches('/'));
+    let response = client
+        .post(&url)
+        .json(&OllamaPullRequest { model: model.to_string(), stream: true })
+        .send()
+        .await
+        .map_err(|e| error_for_request(e, OLLAMA_UNREACHABLE, "Ollama pull timed out."))?;
+    if !response.status().is_success() {
+        return Err(error_from_response("Ollama", response).await);
+    }
+
+    let mut succeeded = false;
+    read_lines(response, |line| {
+        let progress: OllamaPullProgress = serde_json::from_str(line).map_err(malformed)?;
+        if let Some(err) = progress.error.clone() {
+            return Err(ollama_body_error(err));
+        }
+        succeeded |= progress.status == "success";
+        on_progress(&progress);
+        Ok(())
+    })
+    .await?;
+
+    if succeeded {
+        Ok(())
+    } else {
+        Err(AiError::MalformedOutput(format!("Ollama pull of {} ended without success", model)))
+    }
+}
+
+// --- OpenAI-compatible API (llama.cpp server, LM Studio, vLLM, ...) ---
+
+#[derive(Serialize)]
+struct OpenAiRequest {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    model: String,
+    messages: Vec<ChatMessage>,
+    stream: bool,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiResponseMessage {
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiChoice {
+    message: Option<OpenAiResponseMessage>,
+    /// Present instead of `message` in streamed chunks.
+    delta: Option<OpenAiResponseMessage>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiResponse {
+    choices: Vec<OpenAiChoice>,
+}
+
+/// Text carried by one SSE line of a streamed chat completion.
+fn parse_openai_stream_line(line: &str) -> Result<Option<String>, AiError> {
+    let Some(data) = sse_data(line) else {
+        return Ok(None);
+    };
+    if data == "[DONE]" {
+        return Ok(None);
+    }
+    let chunk: OpenAiResponse = serde_json::from_str(data).map_err(malformed)?;
+    Ok(chunk
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|c| c.delta)
+        .and_then(|d| d.content)
+        .filter(|c| !c.is_empty()))
+}
+
+/// Call `{base_url}/chat/completions`, streaming fragments to `on_delta` when given.
+/// `json_schema` is sent as a `json_schema` response format.
+pub async fn call_openai(
+    base_url: &str,
+    api_key: &str,
+    model: &str,
+    messages: &[ChatMessage],
+    options: &GenerationOptions,
+    json_schema: Option<&serde_json::Value>,
+    on_delta: Option<OnDelta<'_>>,
+) -> Result<String, AiError> {
+    let client = build_client(options.timeout)?;
+
+    let request = OpenAiRequest {
+        model: model.to_string(),
+        messages: messages.to_vec(),
+        stream: on_delta.is_some(),
+        max_tokens: options.max_tokens,
+        temperature: options.temperature,
+        response_format: json_schema.map(|schema| {
+            serde_json::json!({
+                "type": "json_schema",
+                "json_schema": { "name": "response", "schema": schema }
+            })
+        }),
+    };
+
+    let url = format!("{}/chat/completions", base_url.trim_end_matches('/'));
+
+    let mut builder = client
+        .post(&url)
+        .header("content-type", "application/json")
+        .json(&request);
+    if !api_key.is_empty() {
+        builder = builder.bearer_auth(api_key);
+    }
+
+    let response = builder.send().await.map_err(|e| {
+        error_for_request(
+            e,
+            &format!("Cannot connect to OpenAI-compatible server at {}", base_url),
+            "OpenAI-compatible server request timed out.",
+        )
+    })?;
+
+    if !response.status().is_success() {
+        return Err(error_from_response("OpenAI-compatible server", response).await);
+    }
+
+    if let Some(on_delta) = on_delta {
+        let mut full = String::new();
+        read_lines(response, |line| {
+            if let Some(text) = parse_openai_stream_line(line)? {
+                on_delta(&text);
+                full.push_str(&text);
+            }
+            Ok(())
+        })
+        .await?;
+        if full.is_empty() {
+            return Err(AiError::MalformedOutput(
+                "Empty response from OpenAI-compatible server".to_string(),
+            ));
+        }
+        return Ok(full);
+    }
+
+    let api_response: OpenAiResponse = response.json().await.map_err(malformed)?;
+
+    api_response
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|c| c.message)
+        .and_then(|m| m.content)
+        .ok_or_else(|| {
+            AiError::MalformedOutput("Empty response from OpenAI-compatible server".to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_stream_decodes_corrected_text() {
+        let mut stream = FieldStream::new("corrected");
+        let fragments = ["{\"corr", "ected\": \"Ich ", "bin\\", "n\\u00e9 \\ud83d", "\\ude00 ", "\\\"ok\\\"", "\", \"edits\": [{\"original\": \"x\"}]}"];
+        let streamed: Vec<String> = fragments.iter().map(|f| stream.push(f)).collect();
+        assert_eq!(streamed, vec!["", "Ich ", "bin", "\né ", "😀 ", "\"ok\"", ""]);
+    }
+
+    #[test]
+    fn test_parse_correction_output_valid() {
+        let raw = r#"{"corrected":"Sono andato al mare.","edits":[{"original":"Io ho andato","replacement":"Sono andato","category":"grammar","reason":"andare takes essere"}]}"#;
+        let output = parse_correction_output(raw).unwrap();
+        assert_eq!(output.corrected, "Sono andato al mare.");
+        assert_eq!(output.edits.len(), 1);
+        assert_eq!(output.edits[0].replacement, "Sono andato");
+    }
+
+    #[test]
+    fn test_parse_correction_output_tolerates_code_fence() {
+        let raw = "Here you go:\n```json\n{\"corrected\": \"今日は晴れです。\", \"edits\": []}\n```";
+        let output = parse_correction_output(raw).unwrap();
+        assert_eq!(output.corrected, "今日は晴れです。");
+        assert!(output.edits.is_empty());
+    }
+
+    #[test]
+    fn test_parse_correction_output_rejects_marker_format() {
+        let raw = "[CORRECTED]\nHello\n[EXPLANATION]\nNone";
+        assert_eq!(parse_correction_output(raw).unwrap_err().code(), "malformed_output");
+    }
+
+    #[test]
+    fn test_parse_correction_output_rejects_missing_corrected() {
+        let raw = r#"{"edits":[]}"#;
+        assert_eq!(parse_correction_output(raw).unwrap_err().code(), "malformed_output");
+    }
+
+    #[test]
+    fn test_parse_vocabulary_output() {
+        let raw = r#"{"items":[{"lemma":"食べる","reading":"たべる","meaning":"to eat","example":"寿司を食べた。"},{"lemma":" ","meaning":"blank"}]}"#;
+        let items = parse_vocabulary_output(raw).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].reading, "たべる");
+        assert_eq!(parse_vocabulary_output("no json").unwrap_err().code(), "malformed_output");
+    }
+
+    #[test]
+    fn test_explanation_from_edits() {
+        let edits = vec![
+            CorrectionEdit {
+                original: "goed".to_string(),
+                replacement: "went".to_string(),
+                category: MistakeCategory::VerbConjugation,
+                reason: "irregular past tense".to_string(),
+            },
+            CorrectionEdit {
+                original: "informations".to_string(),
+                replacement: String::new(),
+                category: MistakeCategory::Other,
+                reason: "uncountable noun".to_string(),
+            },
+        ];
+        assert_eq!(
+            explanation_from_edits(&edits).unwrap(),
+            "1. \"goed\" → \"went\" — irregular past tense\n2. \"informations\" — uncountable noun"
+        );
+        assert_eq!(explanation_from_edits(&[]), None);
+    }
+
+    #[test]
+    fn test_parse_claude_stream_line_tool_input() {
+        let line = r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"{\"corr"}}"#;
+        assert_eq!(parse_claude_stream_line(line).unwrap().as_deref(), Some("{\"corr"));
+    }
+
+    #[test]
+    fn test_correction_prompt_varies_by_strength() {
+        let hint = correction_prompt("Italiano", "English", CorrectionStrength::Hint, MistakeCategory::for_language("it"));
+        let standard = correction_prompt("Italiano", "English", CorrectionStrength::Standard, MistakeCategory::for_language("it"));
+        let full = correction_prompt("Italiano", "English", CorrectionStrength::Full, MistakeCategory::for_language("it"));
+        assert!(hint.contains("DO NOT correct anything"));
+        assert!(standard.contains("Do NOT rephrase"));
+        assert!(full.contains("native speaker wrote it"));
+        for prompt in [&hint, &standard, &full] {
+            assert!(prompt.contains("\"corrected\"") && prompt.contains("\"edits\""));
+        }
+    }
+
+    #[test]
+    fn test_correction_prompt_lists_language_categories() {
+        let ja = correction_prompt("日本語", "English", CorrectionStrength::Standard, MistakeCategory::for_language("ja"));
+        assert!(ja.contains(" particles,") && !ja.contains(" articles,"));
+        let schema = correction_schema(MistakeCategory::for_language("en"));
+        let allowed = &schema["properties"]["edits"]["items"]["properties"]["category"]["enum"];
+        assert!(allowed.as_array().unwrap().contains(&serde_json::json!("articles")));
+    }
+
+    #[test]
+    fn test_parse_claude_stream_line_text_delta() {
+        let line = r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"こん"}}"#;
+        assert_eq!(parse_claude_stream_line(line).unwrap().as_deref(), Some("こん"));
+    }
+
+    #[test]
+    fn test_parse_claude_stream_line_ignores_other_events() {
+        assert_eq!(parse_claude_stream_line("event: content_block_delta").unwrap(), None);
+        let line = r#"data: {"type":"message_stop"}"#;
+        assert_eq!(parse_claude_stream_line(line).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_claude_stream_line_error_event() {
+        let line = r#"data: {"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        let err = parse_claude_stream_line(line).unwrap_err();
+        assert_eq!(err.code(), "overloaded");
+        assert!(err.to_string().contains("Overloaded"));
+    }
+
+    #[test]
+    fn test_parse_ollama_stream_line() {
+        let line = r#"{"model":"gemma2:9b","message":{"role":"assistant","content":"Hi"},"done":false}"#;
+        assert_eq!(parse_ollama_stream_line(line).unwrap().as_deref(), Some("Hi"));
+        let done = r#"{"model":"gemma2:9b","message":{"role":"assistant","content":""},"done":true}"#;
+        assert_eq!(parse_ollama_stream_line(done).unwrap(), None);
+        let err = parse_ollama_stream_line(r#"{"error":"model 'gemma9' not found"}"#).unwrap_err();
+        assert_eq!(err.code(), "model_not_found");
+    }
+
+    fn headers_with_retry_after(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_error_for_status_classifies_codes() {
+        let none = HeaderMap::new();
+        let auth = error_for_status("Claude API", StatusCode::UNAUTHORIZED, &none, "");
+        assert_eq!(auth.code(), "auth");
+        let overloaded = error_for_status("Claude API", StatusCode::from_u16(529).unwrap(), &none, "");
+        assert_eq!(overloaded.code(), "overloaded");
+        let missing = error_for_status("Ollama", StatusCode::NOT_FOUND, &none, r#"{"error":"model 'x' not found"}"#);
+        assert_eq!(missing.code(), "model_not_found");
+        assert!(missing.to_string().contains("model 'x' not found"));
+        let too_long = error_for_status("Claude API", StatusCode::BAD_REQUEST, &none, "");
+        assert_eq!(too_long.code(), "bad_request");
+    }
+
+    #[test]
+    fn test_error_for_status_reads_retry_after() {
+        let headers = headers_with_retry_after("12");
+        let err = error_for_status("Claude API", StatusCode::TOO_MANY_REQUESTS, &headers, "");
+        assert_eq!(err.code(), "rate_limited");
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(12)));
+    }
+
+    #[test]
+    fn test_parse_retry_after_rejects_bad_values() {
+        let parse = |value: &str| parse_retry_after(&headers_with_retry_after(value));
+        assert_eq!(parse("1.5"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse("3600"), Some(MAX_RETRY_DELAY));
+        assert_eq!(parse("1e30"), Some(MAX_RETRY_DELAY));
+        assert_eq!(parse("inf"), None);
+        assert_eq!(parse("NaN"), None);
+        assert_eq!(parse("-1"), None);
+    }
+
+    #[test]
+    fn test_error_detail_extracts_message() {
+        let body = r#"{"type":"error","error":{"type":"rate_limit_error","message":"Slow down"}}"#;
+        assert_eq!(error_detail(body), "Slow down");
+        assert_eq!(error_detail("plain text"), "plain text");
+    }
+
+    #[test]
+    fn test_ollama_model_matches_default_tag() {
+        assert!(ollama_model_matches("gemma2:9b", "gemma2:9b"));
+        assert!(ollama_model_matches("llama3.1", "llama3.1:latest"));
+        assert!(!ollama_model_matches("gemma2:9b", "gemma2:2b"));
+        assert!(!ollama_model_matches("gemma2", "gemma2:9b"));
+    }
+
+    #[test]
+    fn test_parse_ollama_tags() {
+        let body = r#"{"models":[{"name":"gemma2:9b","model":"gemma2:9b","size":5443152417,
+            "details":{"family":"gemma2","parameter_size":"9.2B","quantization_level":"Q4_0"}}]}"#;
+        let tags: OllamaTagsResponse = serde_json::from_str(body).unwrap();
+        assert_eq!(tags.models.len(), 1);
+        assert_eq!(tags.models[0].size, 5443152417);
+        assert_eq!(tags.models[0].details.family.as_deref(), Some("gemma2"));
+    }
+
+    #[test]
+    fn test_parse_openai_stream_line() {
+        let line = r#"data: {"choices":[{"index":0,"delta":{"content":"Ciao"}}]}"#;
+        assert_eq!(parse_openai_stream_line(line).unwrap().as_deref(), Some("Ciao"));
+        assert_eq!(parse_openai_stream_line("data: [DONE]").unwrap(), None);
+        assert_eq!(parse_openai_stream_line(": keep-alive").unwrap(), None);
+    }
 }
@@ -1,8 +1,25 @@
-use tauri::command;
+use tauri::{command, AppHandle, Emitter, State};
 use std::collections::HashMap;
-use crate::claude;
-use crate::config::{self, AppConfig};
+use std::sync::Mutex;
+use crate::anki;
+use crate::atomic;
+use crate::audit::{self, AuditRecord};
+use crate::claude::{self, OnDelta};
+use crate::diff::{self, DiffHunk};
+use crate::entry_id::EntryId;
+use crate::history::{self, RevisionInfo, SectionDiff};
+use crate::config::{self, AppConfig, CorrectionStrength};
+use crate::inflight::InFlightRequests;
+use crate::mistakes::{self, MistakeCategory, MistakeStats};
+use crate::provider::{self, AiError, AiTask, ChatOptions, ProviderCapabilities};
+use crate::redact::Redactor;
+use crate::reviews::{self, ReviewCard, ReviewGrade};
+use crate::search::{self, SearchFilters, SearchResult};
+use crate::secrets::{self, SecretStatus};
 use crate::storage::{self, DiaryEntry, EntryListItem, EntryMeta};
+use crate::trash::{self, TrashItem};
+use crate::vault::{self, VaultStatus};
+use crate::vocabulary::{self, NewVocabItem, VocabItem};
 
 #[command]
 pub async fn list_entries(year: i32, month: u32) -> Result<Vec<EntryListItem>, String> {
@@ -10,18 +27,23 @@ pub async fn list_entries(year: i32, month: u32) -> Result<Vec<EntryListItem>, S
 }
 
 #[command]
-pub async fn search_entries(query: String) -> Result<Vec<EntryListItem>, String> {
-    storage::search_entries(&query)
+pub async fn search_entries(query: String, filters: Option<SearchFilters>) -> Result<Vec<SearchResult>, String> {
+    search::search(&query, filters.unwrap_or_default())
 }
 
 #[command]
-pub async fn read_entry(id: String) -> Result<DiaryEntry, String> {
+pub async fn rebuild_search_index() -> Result<usize, String> {
+    search::rebuild()
+}
+
+#[command]
+pub async fn read_entry(id: EntryId) -> Result<DiaryEntry, String> {
     storage::read_entry_by_id(&id)
 }
 
 #[command]
 pub async fn save_entry(
-    id: Option<String>,
+    id: Option<EntryId>,
     title: String,
     date: String,
     mode: String,
@@ -30,10 +52,23 @@ pub async fn save_entry(
     translations: HashMap<String, String>,
     date_format: Option<String>,
     created_at: Option<String>,
+    correction_strength: Option<CorrectionStrength>,
+    explanations: Option<HashMap<String, String>>,
+    edits: Option<HashMap<String, Vec<claude::CorrectionEdit>>>,
+    private: Option<bool>,
 ) -> Result<String, String> {
     let now = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
     let is_new = id.is_none();
-    let entry_id = id.unwrap_or_else(|| storage::generate_entry_id(&date));
+    // Left out by the caller: keep whatever the saved entry has
+    let private = match (private, &id) {
+        (Some(private), _) => private,
+        (None, Some(id)) => storage::is_entry_private(id)?,
+        (None, None) => false,
+    };
+    let entry_id = match id {
+        Some(id) => id.to_string(),
+        None => storage::generate_entry_id(&date),
+    };
 
     let final_created_at = if is_new {
         Some(now.clone())
@@ -61,86 +96,231 @@ pub async fn save_entry(
             date_format,
             created_at: final_created_at,
             updated_at: Some(now),
+            correction_strength,
+            private,
         },
         original,
         translations,
+        explanations: explanations.unwrap_or_default(),
+        edits: edits.unwrap_or_default(),
     };
     storage::save_entry_to_disk(&entry)?;
     Ok(entry_id)
 }
 
+/// Saved revisions of an entry, newest first.
+#[command]
+pub async fn list_revisions(id: EntryId) -> Result<Vec<RevisionInfo>, String> {
+    history::list(&id)
+}
+
+/// Changes from revision `from` to revision `to`, or to the current entry when `to` is None.
+#[command]
+pub async fn diff_revisions(id: EntryId, from: String, to: Option<String>) -> Result<Vec<SectionDiff>, String> {
+    let old = history::read(&id, &from)?;
+    let new = match to {
+        Some(revision) => history::read(&id, &revision)?,
+        None => storage::read_entry_by_id(&id)?,
+    };
+    Ok(history::diff_entries(&old, &new))
+}
+
+/// Make an old revision the current entry. The entry as it was before is kept as a
+/// revision too, so a restore can itself be undone.
+#[command]
+pub async fn restore_revision(id: EntryId, revision: String) -> Result<DiaryEntry, String> {
+    let mut entry = history::read(&id, &revision)?;
+    entry.meta.updated_at = Some(chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string());
+    storage::save_entry_to_disk(&entry)?;
+    Ok(entry)
+}
+
 #[command]
-pub async fn save_image(id: String, filename: String, data: Vec<u8>) -> Result<String, String> {
+pub async fn save_image(id: EntryId, filename: String, data: Vec<u8>) -> Result<String, String> {
     storage::save_image_to_disk(&id, &filename, &data)
 }
 
 #[command]
-pub async fn delete_entry(id: String) -> Result<(), String> {
+pub async fn read_image(id: EntryId, filename: String) -> Result<Vec<u8>, String> {
+    storage::read_image_from_disk(&id, &filename)
+}
+
+#[command]
+pub async fn delete_entry(id: EntryId) -> Result<(), String> {
     storage::delete_entry_from_disk(&id)
 }
 
+/// Trashed entries, most recently deleted first. Expired ones are purged first.
+#[command]
+pub async fn list_trash() -> Result<Vec<TrashItem>, String> {
+    trash::purge_expired()?;
+    trash::list()
+}
+
+#[command]
+pub async fn restore_entry(id: EntryId) -> Result<DiaryEntry, String> {
+    trash::restore(&id)
+}
+
+/// Permanently delete everything in the trash. Returns the number of entries removed.
+#[command]
+pub async fn empty_trash() -> Result<usize, String> {
+    trash::empty()
+}
+
 #[command]
 pub async fn create_entry_id(date: String) -> String {
     storage::generate_entry_id(&date)
 }
 
+/// Event carrying incremental AI output for a request started with a `request_id`.
+pub const AI_STREAM_EVENT: &str = "ai-stream";
+
+#[derive(Clone, serde::Serialize)]
+pub struct StreamChunk {
+    pub request_id: String,
+    /// Target language for `translate_text`; None for corrections.
+    pub language: Option<String>,
+    pub delta: String,
+}
+
+fn emit_chunk(app: &AppHandle, request_id: &str, language: Option<&str>, delta: &str) {
+    let chunk = StreamChunk {
+        request_id: request_id.to_string(),
+        language: language.map(String::from),
+        delta: delta.to_string(),
+    };
+    if let Err(e) = app.emit(AI_STREAM_EVENT, chunk) {
+        log::warn!("Failed to emit {}: {}", AI_STREAM_EVENT, e);
+    }
+}
+
 #[derive(serde::Serialize)]
 pub struct CorrectionResult {
     pub corrected: String,
+    /// Human-readable list built from `edits`, for display.
     pub explanation: Option<String>,
+    pub edits: Vec<claude::CorrectionEdit>,
+    /// Diff of the submitted text against `corrected`.
+    pub hunks: Vec<DiffHunk>,
+    /// Level actually used, to be passed back to `save_entry`.
+    pub strength: CorrectionStrength,
 }
 
+/// Correct `text` at the given `strength` (default: the one in Settings). When
+/// `request_id` is given the corrected text is also streamed as `ai-stream`
+/// events, and the request can be aborted with `cancel_ai_request`.
 #[command]
-pub async fn correct_text(text: String, language: String, explanation_language: String) -> Result<CorrectionResult, String> {
-    let config = config::load_app_config();
+pub async fn correct_text(
+    app: AppHandle,
+    requests: State<'_, InFlightRequests>,
+    text: String,
+    language: String,
+    explanation_language: String,
+    strength: Option<CorrectionStrength>,
+    entry_id: Option<EntryId>,
+    request_id: Option<String>,
+) -> Result<CorrectionResult, AiError> {
+    let config = config::load_with_secrets().map_err(AiError::NotConfigured)?;
     let lang_name = config
         .languages
         .iter()
         .find(|l| l.code == language)
         .map(|l| l.name.clone())
-        .unwrap_or(language);
-    let system = claude::correction_prompt(&lang_name, &explanation_language);
+        .unwrap_or_else(|| language.clone());
+    let strength = strength.unwrap_or(config.correction_strength);
+    let categories = MistakeCategory::for_language(&language);
+    let system = claude::correction_prompt(&lang_name, &explanation_language, strength, categories);
 
-    let raw = match config.ai_provider.as_str() {
-        "claude" => {
-            if config.api_key.is_empty() {
-                return Err("API key not configured. Please set it in Settings.".to_string());
-            }
-            claude::call_claude(&config.api_key, &system, &text).await?
-        }
-        _ => {
-            claude::call_ollama(&config.ollama_url, &config.ollama_model, &system, &text).await?
+    let ai = provider::resolve(&config, AiTask::Correction)?;
+    let mut redactor = Redactor::for_provider(&config, ai.as_ref());
+    let sent = redactor.redact(&text);
+    let system = redactor.annotate_prompt(system);
+    audit::before_send(ai.as_ref(), entry_id.as_ref(), AiTask::Correction, sent.chars().count())?;
+    // Only the corrected text is streamed, still with placeholders; the final result
+    // has them restored. Hint mode streams nothing, as the model may leak the answer.
+    let corrected_stream = Mutex::new(claude::FieldStream::new("corrected"));
+    let on_delta = |delta: &str| {
+        let Some(rid) = &request_id else { return };
+        let text = match corrected_stream.lock() {
+            Ok(mut stream) => stream.push(delta),
+            Err(_) => return,
+        };
+        if !text.is_empty() {
+            emit_chunk(&app, rid, None, &text);
         }
     };
+    let schema = claude::correction_schema(categories);
+    let streaming = request_id.is_some() && strength != CorrectionStrength::Hint;
+    let options = ChatOptions {
+        json_schema: Some(&schema),
+        on_delta: streaming.then_some(&on_delta as OnDelta),
+    };
+    let raw = requests
+        .run(request_id.as_deref(), ai.complete(&system, &sent, options))
+        .await??;
 
-    // Parse [CORRECTED] / [EXPLANATION] sections
-    let (corrected, explanation) = if let Some(corr_start) = raw.find("[CORRECTED]") {
-        let after_marker = &raw[corr_start + "[CORRECTED]".len()..];
-        if let Some(expl_start) = after_marker.find("[EXPLANATION]") {
-            let corrected = after_marker[..expl_start].trim().to_string();
-            let explanation = after_marker[expl_start + "[EXPLANATION]".len()..].trim().to_string();
-            (corrected, if explanation.is_empty() { None } else { Some(explanation) })
-        } else {
-            (after_marker.trim().to_string(), None)
-        }
+    let mut output = claude::parse_correction_output(&raw)?;
+    output.corrected = redactor.restore(&output.corrected);
+    for edit in &mut output.edits {
+        edit.original = redactor.restore(&edit.original);
+        edit.replacement = redactor.restore(&edit.replacement);
+        edit.reason = redactor.restore(&edit.reason);
+    }
+    mistakes::normalize_edits(&mut output.edits, &language);
+    // Hint mode must never reveal the answer, whatever the model returned
+    let corrected = if strength == CorrectionStrength::Hint {
+        text.clone()
     } else {
-        // Fallback: treat entire response as corrected text (backward compat)
-        (raw.trim().to_string(), None)
+        output.corrected.trim().to_string()
     };
+    let explanation = claude::explanation_from_edits(&output.edits);
+    let hunks = diff::diff_text(&text, &corrected, &language);
+
+    Ok(CorrectionResult { corrected, explanation, edits: output.edits, hunks, strength })
+}
+
+/// Most frequent mistake categories and their month-by-month counts, from the
+/// edit lists saved in every entry. `language` limits the report to one language.
+#[command]
+pub async fn get_mistake_stats(language: Option<String>) -> Result<MistakeStats, String> {
+    let entries = storage::read_all_entries()?;
+    Ok(mistakes::compute_stats(&entries, language.as_deref()))
+}
 
-    Ok(CorrectionResult { corrected, explanation })
+/// Diff two texts the same way `correct_text` does, e.g. for a correction loaded from disk.
+/// Async so that long texts are not diffed on the main thread.
+#[command]
+pub async fn diff_text(original: String, corrected: String, language: String) -> Vec<DiffHunk> {
+    diff::diff_text(&original, &corrected, &language)
+}
+
+/// One language's result from `translate_text`: the translation, or why it failed.
+#[derive(serde::Serialize)]
+pub struct TranslationOutcome {
+    pub text: Option<String>,
+    pub error: Option<AiError>,
 }
 
+/// Translate `text` into every target language in parallel. A language that fails
+/// does not fail the others. With a `request_id`, each language streams its own
+/// `ai-stream` events tagged with its code, and `cancel_ai_request` aborts all of
+/// the parallel translations at once.
 #[command]
 pub async fn translate_text(
+    app: AppHandle,
+    requests: State<'_, InFlightRequests>,
     text: String,
     target_languages: Vec<String>,
-) -> Result<HashMap<String, String>, String> {
-    let config = config::load_app_config();
-
-    if config.ai_provider == "claude" && config.api_key.is_empty() {
-        return Err("API key not configured. Please set it in Settings.".to_string());
-    }
+    entry_id: Option<EntryId>,
+    request_id: Option<String>,
+) -> Result<HashMap<String, TranslationOutcome>, AiError> {
+    let config = config::load_with_secrets().map_err(AiError::NotConfigured)?;
+    let ai = provider::resolve(&config, AiTask::Translation)?;
+    let mut redactor = Redactor::for_provider(&config, ai.as_ref());
+    let sent = redactor.redact(&text);
+    let chars = sent.chars().count() * target_languages.len();
+    audit::before_send(ai.as_ref(), entry_id.as_ref(), AiTask::Translation, chars)?;
 
     // Spawn all translations in parallel
     let futures: Vec<_> = target_languages.iter().map(|lang_code| {
@@ -150,38 +330,313 @@ pub async fn translate_text(
             .find(|l| &l.code == lang_code)
             .map(|l| l.name.clone())
             .unwrap_or_else(|| lang_code.clone());
-        let system = claude::translation_prompt(&lang_name);
-        let text = text.clone();
-        let api_key = config.api_key.clone();
-        let provider = config.ai_provider.clone();
-        let ollama_url = config.ollama_url.clone();
-        let ollama_model = config.ollama_model.clone();
+        let system = redactor.annotate_prompt(claude::translation_prompt(&lang_name));
+        let text = &sent;
+        let ai = &ai;
+        let app = &app;
+        let request_id = request_id.as_deref();
         let lang_code = lang_code.clone();
 
         async move {
-            let result = match provider.as_str() {
-                "claude" => claude::call_claude(&api_key, &system, &text).await,
-                _ => claude::call_ollama(&ollama_url, &ollama_model, &system, &text).await,
+            let on_delta = |delta: &str| {
+                if let Some(rid) = request_id {
+                    emit_chunk(app, rid, Some(&lang_code), delta);
+                }
+            };
+            let options = ChatOptions {
+                on_delta: request_id.map(|_| &on_delta as OnDelta),
+                ..ChatOptions::default()
             };
+            let result = ai.complete(&system, text, options).await;
             (lang_code, result)
         }
     }).collect();
 
-    let results_vec = futures::future::join_all(futures).await;
-    let mut results = HashMap::new();
-    for (lang_code, result) in results_vec {
-        match result {
-            Ok(text) => { results.insert(lang_code, text); }
-            Err(e) => { results.insert(lang_code, format!("[Translation failed: {}]", e)); }
-        }
+    let results_vec = requests
+        .run(request_id.as_deref(), futures::future::join_all(futures))
+        .await?;
+    let results = results_vec
+        .into_iter()
+        .map(|(lang_code, result)| {
+            let outcome = match result {
+                Ok(text) => TranslationOutcome { text: Some(redactor.restore(&text)), error: None },
+                Err(e) => TranslationOutcome { text: None, error: Some(e) },
+            };
+            (lang_code, outcome)
+        })
+        .collect();
+    Ok(results)
+}
+
+// --- Vocabulary notebook ---
+
+/// Suggest words worth studying in `text` (a translation or corrected text in
+/// `language`), including the expressions fixed by `edits`. Words already in the
+/// notebook are left out, and nothing is saved until `add_vocabulary`.
+#[command]
+pub async fn extract_vocabulary(
+    requests: State<'_, InFlightRequests>,
+    text: String,
+    language: String,
+    explanation_language: String,
+    edits: Option<Vec<claude::CorrectionEdit>>,
+    entry_id: Option<EntryId>,
+    request_id: Option<String>,
+) -> Result<Vec<NewVocabItem>, AiError> {
+    let config = config::load_with_secrets().map_err(AiError::NotConfigured)?;
+    let lang_name = config
+        .languages
+        .iter()
+        .find(|l| l.code == language)
+        .map(|l| l.name.clone())
+        .unwrap_or_else(|| language.clone());
+    let system = claude::vocabulary_prompt(&lang_name, &explanation_language);
+
+    let mut user_text = text;
+    let edits = edits.unwrap_or_default();
+    let corrections: Vec<String> = edits
+        .iter()
+        .filter(|e| !e.replacement.is_empty())
+        .map(|e| format!("- \"{}\" → \"{}\": {}", e.original, e.replacement, e.reason))
+        .collect();
+    if !corrections.is_empty() {
+        user_text.push_str("\n\nCorrections:\n");
+        user_text.push_str(&corrections.join("\n"));
     }
 
-    Ok(results)
+    let ai = provider::resolve(&config, AiTask::Vocabulary)?;
+    let mut redactor = Redactor::for_provider(&config, ai.as_ref());
+    let sent = redactor.redact(&user_text);
+    let system = redactor.annotate_prompt(system);
+    audit::before_send(ai.as_ref(), entry_id.as_ref(), AiTask::Vocabulary, sent.chars().count())?;
+    let schema = claude::vocabulary_schema();
+    let options = ChatOptions { json_schema: Some(&schema), ..ChatOptions::default() };
+    let raw = requests
+        .run(request_id.as_deref(), ai.complete(&system, &sent, options))
+        .await??;
+
+    // A notebook that cannot be read only disables the duplicate filter here
+    let notebook = vocabulary::read().unwrap_or_default();
+    let candidates = claude::parse_vocabulary_output(&raw)?
+        .into_iter()
+        .map(|c| NewVocabItem {
+            language: language.clone(),
+            lemma: redactor.restore(c.lemma.trim()),
+            reading: Some(redactor.restore(&c.reading)).filter(|r| !r.trim().is_empty()),
+            meaning: redactor.restore(&c.meaning),
+            example: Some(redactor.restore(&c.example)).filter(|e| !e.trim().is_empty()),
+            source_entry_id: entry_id.as_ref().map(|id| id.to_string()),
+            tags: vec![],
+        })
+        .filter(|item| !notebook.contains(&item.language, &item.lemma))
+        .collect();
+    Ok(candidates)
+}
+
+/// Save words to the notebook. Returns the ones added; duplicates are skipped.
+#[command]
+pub async fn add_vocabulary(items: Vec<NewVocabItem>) -> Result<Vec<VocabItem>, String> {
+    vocabulary::modify(|notebook, now| Ok(notebook.add(items, now)))
+}
+
+#[command]
+pub async fn list_vocabulary(language: Option<String>, tag: Option<String>) -> Result<Vec<VocabItem>, String> {
+    Ok(vocabulary::read()?.list(language.as_deref(), tag.as_deref()))
+}
+
+#[command]
+pub async fn update_vocabulary(item: VocabItem) -> Result<VocabItem, String> {
+    vocabulary::modify(|notebook, now| notebook.update(item, now))
+}
+
+/// Add and remove tags on several words. Returns how many words changed.
+#[command]
+pub async fn tag_vocabulary(ids: Vec<u64>, add: Vec<String>, remove: Vec<String>) -> Result<usize, String> {
+    vocabulary::modify(|notebook, now| Ok(notebook.tag(&ids, &add, &remove, now)))
+}
+
+#[command]
+pub async fn delete_vocabulary(ids: Vec<u64>) -> Result<usize, String> {
+    vocabulary::modify(|notebook, _| Ok(notebook.delete(&ids)))
+}
+
+// --- Anki export ---
+
+/// Write an Anki-importable TSV of the corrections in entries dated `from`..=`to`
+/// to `path`, plus vocabulary words from that period unless `include_vocabulary` is false.
+/// Entries that cannot be read are left out and listed in the summary.
+#[command]
+pub async fn export_anki(
+    from: String,
+    to: String,
+    path: String,
+    include_vocabulary: Option<bool>,
+) -> Result<anki::AnkiExportSummary, String> {
+    let (entries, skipped) = storage::read_entries_in_range(&from, &to)?;
+    let words: Vec<VocabItem> = if include_vocabulary.unwrap_or(true) {
+        vocabulary::read()?
+            .items
+            .into_iter()
+            .filter(|item| {
+                // The source entry's date, else the day the word was saved
+                let date = item.source_entry_id.as_deref().unwrap_or(&item.created_at);
+                date.get(..10).is_some_and(|d| d >= from.as_str() && d <= to.as_str())
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+    let (tsv, mut summary) = anki::build_tsv(&entries, &words);
+    atomic::write_file(std::path::Path::new(&path), tsv.as_bytes(), false)?;
+    summary.skipped = skipped;
+    Ok(summary)
+}
+
+// --- Reviews ---
+
+/// Cards due today or earlier, most overdue first. New corrections and vocabulary
+/// words become cards here, and cards whose entry or word was deleted are dropped.
+#[command]
+pub async fn get_due_reviews(limit: Option<usize>, language: Option<String>) -> Result<Vec<ReviewCard>, String> {
+    let entries = storage::read_all_entries()?;
+    let words = vocabulary::read()?.items;
+    let today = chrono::Local::now().date_naive();
+    reviews::modify(|deck| {
+        deck.sync(&entries, &words, today);
+        Ok(deck.due(today, language.as_deref(), limit))
+    })
+}
+
+/// Record how well a card was remembered and schedule its next review.
+#[command]
+pub async fn grade_review(card_id: String, grade: ReviewGrade) -> Result<ReviewCard, String> {
+    let today = chrono::Local::now().date_naive();
+    reviews::modify(|deck| deck.grade(&card_id, grade, today))
+}
+
+/// Abort a running `correct_text` / `translate_text` call. The aborted command
+/// fails with error code `"cancelled"`. Returns false if the request already finished.
+#[command]
+pub async fn cancel_ai_request(
+    requests: State<'_, InFlightRequests>,
+    request_id: String,
+) -> Result<bool, String> {
+    Ok(requests.cancel(&request_id))
+}
+
+#[derive(serde::Serialize)]
+pub struct OllamaStatus {
+    pub url: String,
+    pub reachable: bool,
+    pub version: Option<String>,
+    pub models: Vec<claude::OllamaModel>,
+    pub configured_model: String,
+    pub configured_model_installed: bool,
+    /// Why Ollama could not be queried, when `reachable` is false.
+    pub error: Option<AiError>,
+}
+
+/// Health check for the Settings page: is Ollama running, which models are
+/// installed, and is the one configured in `ollama_model` among them.
+#[command]
+pub async fn check_ollama() -> Result<OllamaStatus, String> {
+    let config = config::load_app_config()?;
+    let url = config.ollama_url.clone();
+    let listed = futures::future::try_join(
+        claude::ollama_version(&url),
+        claude::ollama_list_models(&url),
+    )
+    .await;
+
+    Ok(match listed {
+        Ok((version, models)) => OllamaStatus {
+            configured_model_installed: models
+                .iter()
+                .any(|m| claude::ollama_model_matches(&config.ollama_model, &m.name)),
+            url,
+            reachable: true,
+            version: Some(version),
+            models,
+            configured_model: config.ollama_model,
+            error: None,
+        },
+        Err(e) => OllamaStatus {
+            url,
+            reachable: false,
+            version: None,
+            models: vec![],
+            configured_model: config.ollama_model,
+            configured_model_installed: false,
+            error: Some(e),
+        },
+    })
+}
+
+/// Event carrying `ollama pull` progress for `pull_ollama_model`.
+pub const OLLAMA_PULL_EVENT: &str = "ollama-pull-progress";
+
+#[derive(Clone, serde::Serialize)]
+pub struct PullProgressEvent {
+    pub request_id: Option<String>,
+    pub model: String,
+    #[serde(flatten)]
+    pub progress: claude::OllamaPullProgress,
+}
+
+/// Download a model into the local Ollama, emitting `ollama-pull-progress` events.
+/// A pull started with a `request_id` can be stopped with `cancel_ai_request`.
+#[command]
+pub async fn pull_ollama_model(
+    app: AppHandle,
+    requests: State<'_, InFlightRequests>,
+    model: String,
+    request_id: Option<String>,
+) -> Result<(), AiError> {
+    let config = config::load_app_config().map_err(AiError::NotConfigured)?;
+    let on_progress = |progress: &claude::OllamaPullProgress| {
+        let event = PullProgressEvent {
+            request_id: request_id.clone(),
+            model: model.clone(),
+            progress: progress.clone(),
+        };
+        if let Err(e) = app.emit(OLLAMA_PULL_EVENT, event) {
+            log::warn!("Failed to emit {}: {}", OLLAMA_PULL_EVENT, e);
+        }
+    };
+    requests
+        .run(
+            request_id.as_deref(),
+            claude::ollama_pull(&config.ollama_url, &model, &on_progress),
+        )
+        .await?
+}
+
+#[derive(serde::Serialize)]
+pub struct ProviderInfo {
+    pub id: String,
+    pub capabilities: ProviderCapabilities,
+}
+
+/// The provider currently selected in Settings, for the status indicator in the main UI.
+#[command]
+pub async fn get_ai_provider() -> Result<ProviderInfo, String> {
+    let config = config::load_with_secrets()?;
+    let ai = provider::resolve(&config, AiTask::Correction)?;
+    Ok(ProviderInfo {
+        id: ai.id().to_string(),
+        capabilities: ai.capabilities(),
+    })
+}
+
+/// What diary text was sent to remote AI providers, newest first.
+#[command]
+pub async fn get_audit_log(limit: Option<usize>) -> Result<Vec<AuditRecord>, String> {
+    audit::list(limit.unwrap_or(200))
 }
 
 #[command]
 pub async fn load_config() -> Result<AppConfig, String> {
-    Ok(config::load_app_config())
+    config::load_app_config()
 }
 
 #[command]
@@ -189,9 +644,77 @@ pub async fn save_config(config: AppConfig) -> Result<(), String> {
     config::save_app_config(&config)
 }
 
+/// Where API keys are stored and which ones are set; the keys themselves never
+/// leave the backend.
+#[command]
+pub async fn get_secret_status() -> Result<SecretStatus, String> {
+    Ok(secrets::status())
+}
+
+/// Create the encrypted secrets file used when there is no OS keychain, with a
+/// passphrase the user typed twice.
+#[command]
+pub async fn init_secrets(passphrase: String, confirmation: String) -> Result<SecretStatus, String> {
+    secrets::init(&passphrase, &confirmation)?;
+    // Plaintext keys waiting for an unlocked store are moved now
+    config::load_app_config()?;
+    Ok(secrets::status())
+}
+
+/// Unlock the encrypted secrets file used when there is no OS keychain.
+#[command]
+pub async fn unlock_secrets(passphrase: String) -> Result<SecretStatus, String> {
+    secrets::unlock(&passphrase)?;
+    // Plaintext keys waiting for an unlocked store are moved now
+    config::load_app_config()?;
+    Ok(secrets::status())
+}
+
+/// Set or, with an empty key, remove the API key of `provider`.
+#[command]
+pub async fn set_api_key(provider: String, key: String) -> Result<SecretStatus, String> {
+    let name = match provider.as_str() {
+        "claude" => secrets::CLAUDE_API_KEY,
+        "openai" => secrets::OPENAI_API_KEY,
+        _ => return Err(format!("Provider \"{}\" does not use an API key", provider)),
+    };
+    secrets::set(name, key.trim())?;
+    Ok(secrets::status())
+}
+
+#[command]
+pub async fn get_vault_status() -> Result<VaultStatus, String> {
+    vault::status()
+}
+
+#[command]
+pub async fn unlock_vault(passphrase: String) -> Result<VaultStatus, String> {
+    vault::unlock(&passphrase)?;
+    vault::status()
+}
+
+#[command]
+pub async fn lock_vault() -> Result<VaultStatus, String> {
+    vault::lock()?;
+    vault::status()
+}
+
+/// Turn on encryption for the entries folder, encrypting what is already there.
+/// Returns the number of files encrypted.
+#[command]
+pub async fn enable_vault(passphrase: String) -> Result<usize, String> {
+    vault::enable(&passphrase)
+}
+
+/// Decrypt the entries folder and turn encryption off. Returns the number of files decrypted.
+#[command]
+pub async fn disable_vault(passphrase: String) -> Result<usize, String> {
+    vault::disable(&passphrase)
+}
+
 #[command]
 pub async fn get_entries_dir() -> Result<String, String> {
-    let config = config::load_app_config();
+    let config = config::load_app_config()?;
     Ok(config.entries_dir)
 }
 
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::atomic;
+use crate::secrets;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LanguageConfig {
@@ -9,9 +12,54 @@ pub struct LanguageConfig {
     pub date_format: String,
 }
 
+/// How much the AI intervenes when correcting (design decision D3).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CorrectionStrength {
+    /// Only point out problem spans; the text itself is left unchanged.
+    #[serde(alias = "hints")]
+    Hint,
+    /// Fix grammar, spelling and wrong word choice.
+    #[default]
+    Standard,
+    /// Also rewrite for natural, native-sounding phrasing.
+    Full,
+}
+
+impl CorrectionStrength {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CorrectionStrength::Hint => "hint",
+            CorrectionStrength::Standard => "standard",
+            CorrectionStrength::Full => "full",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "hint" | "hints" => Some(CorrectionStrength::Hint),
+            "standard" => Some(CorrectionStrength::Standard),
+            "full" => Some(CorrectionStrength::Full),
+            _ => None,
+        }
+    }
+}
+
+/// Per-task overrides of the generation settings. Unset fields fall back to the
+/// global values in `AppConfig`; `model` applies to whichever provider is active.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskOverrides {
+    pub model: Option<String>,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    pub timeout_secs: Option<u64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
-    #[serde(default)]
+    /// Write-only: accepted from Settings and moved to the secret store, never
+    /// written to config.json or sent back. Filled in by `load_with_secrets`.
+    #[serde(default, skip_serializing)]
     pub api_key: String,
     #[serde(default = "default_language")]
     pub default_language: String,
@@ -28,6 +76,44 @@ pub struct AppConfig {
     pub ollama_model: String,
     #[serde(default = "default_ollama_url")]
     pub ollama_url: String,
+    /// Base URL of an OpenAI-compatible server, including the `/v1` prefix.
+    #[serde(default = "default_openai_url")]
+    pub openai_url: String,
+    #[serde(default)]
+    pub openai_model: String,
+    /// Optional bearer token; most local servers accept requests without one.
+    /// Write-only like `api_key`.
+    #[serde(default, skip_serializing)]
+    pub openai_api_key: String,
+    #[serde(default = "default_claude_model")]
+    pub claude_model: String,
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: u32,
+    /// None leaves the provider's own default in place.
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default)]
+    pub correction_overrides: TaskOverrides,
+    #[serde(default)]
+    pub translation_overrides: TaskOverrides,
+    /// Used when `correct_text` is called without an explicit strength.
+    #[serde(default)]
+    pub correction_strength: CorrectionStrength,
+    /// Revisions kept per entry; 0 turns revision history off.
+    #[serde(default = "default_history_retention")]
+    pub history_retention: u32,
+    /// Days deleted entries stay in the trash; 0 keeps them until the trash is emptied.
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: u32,
+    /// Replace names, emails, phone numbers and addresses with placeholders before
+    /// text goes to a remote provider.
+    #[serde(default)]
+    pub redact_pii: bool,
+    /// Names to redact in addition to the detected details.
+    #[serde(default)]
+    pub redaction_names: Vec<String>,
 }
 
 fn default_language() -> String {
@@ -41,6 +127,12 @@ fn default_theme() -> String {
 fn default_ai_provider() -> String { "ollama".to_string() }
 fn default_ollama_model() -> String { "gemma2:9b".to_string() }
 fn default_ollama_url() -> String { "http://localhost:11434".to_string() }
+fn default_openai_url() -> String { "http://localhost:8080/v1".to_string() }
+fn default_claude_model() -> String { "claude-haiku-4-5-20251001".to_string() }
+fn default_max_tokens() -> u32 { 4096 }
+fn default_timeout_secs() -> u64 { 120 }
+fn default_history_retention() -> u32 { 50 }
+fn default_trash_retention_days() -> u32 { 30 }
 
 fn default_entries_dir() -> String {
     dirs::document_dir()
@@ -83,6 +175,20 @@ impl Default for AppConfig {
             ai_provider: default_ai_provider(),
             ollama_model: default_ollama_model(),
             ollama_url: default_ollama_url(),
+            openai_url: default_openai_url(),
+            openai_model: String::new(),
+            openai_api_key: String::new(),
+            claude_model: default_claude_model(),
+            max_tokens: default_max_tokens(),
+            temperature: None,
+            timeout_secs: default_timeout_secs(),
+            correction_overrides: TaskOverrides::default(),
+            translation_overrides: TaskOverrides::default(),
+            correction_strength: CorrectionStrength::default(),
+            history_retention: default_history_retention(),
+            trash_retention_days: default_trash_retention_days(),
+            redact_pii: false,
+            redaction_names: vec![],
         }
     }
 }
@@ -94,21 +200,223 @@ pub fn config_path() -> PathBuf {
         .join("config.json")
 }
 
-pub fn load_app_config() -> AppConfig {
-    let path = config_path();
-    if path.exists() {
-        let content = fs::read_to_string(&path).unwrap_or_default();
-        serde_json::from_str(&content).unwrap_or_default()
-    } else {
-        AppConfig::default()
+fn read_config(path: &Path) -> Result<AppConfig, String> {
+    if !path.exists() {
+        return Ok(AppConfig::default());
     }
+    let content = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| {
+        let backup = atomic::backup_path(path);
+        let mut message = format!("Config file {} is corrupted: {}", path.display(), e);
+        if backup.exists() {
+            message.push_str(&format!(". The previous version is saved in {}", backup.display()));
+        }
+        message
+    })
 }
 
-pub fn save_app_config(config: &AppConfig) -> Result<(), String> {
+/// Plaintext keys in `config`, paired with the secret names they belong under.
+fn plaintext_keys(config: &AppConfig) -> Vec<(&'static str, &str)> {
+    [(secrets::CLAUDE_API_KEY, config.api_key.as_str()), (secrets::OPENAI_API_KEY, config.openai_api_key.as_str())]
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .collect()
+}
+
+/// Move keys left in a config file by older versions into `store`, then rewrite the
+/// file without them. The backup is dropped too when it still holds keys.
+fn migrate_keys(
+    path: &Path,
+    config: &mut AppConfig,
+    mut store: impl FnMut(&str, &str) -> Result<(), String>,
+) -> Result<(), String> {
+    let keys = plaintext_keys(config);
+    if keys.is_empty() {
+        return Ok(());
+    }
+    for (name, value) in keys {
+        store(name, value)?;
+    }
+    let json = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    atomic::write_file(path, json.as_bytes(), false)?;
+    let backup = atomic::backup_path(path);
+    if read_config(&backup).is_ok_and(|old| !plaintext_keys(&old).is_empty()) {
+        fs::remove_file(&backup).map_err(|e| e.to_string())?;
+    }
+    config.api_key.clear();
+    config.openai_api_key.clear();
+    Ok(())
+}
+
+/// Read the config, moving any plaintext keys to the secret store. They stay in
+/// the returned config only when that failed.
+fn load_and_migrate() -> Result<AppConfig, String> {
+    static MIGRATION_WARNED: AtomicBool = AtomicBool::new(false);
     let path = config_path();
+    let mut config = read_config(&path)?;
+    if let Err(e) = migrate_keys(&path, &mut config, secrets::set) {
+        if !MIGRATION_WARNED.swap(true, Ordering::Relaxed) {
+            log::warn!("Could not move API keys out of {}: {}", path.display(), e);
+        }
+    }
+    Ok(config)
+}
+
+/// The saved config, or defaults when there is none yet. A config file that cannot
+/// be read is an error rather than a silent reset, since saving the defaults over it
+/// would lose every setting.
+///
+/// API keys are never included. Plaintext keys from older versions are moved to the
+/// secret store here; while that is not possible (a locked secrets file) they are
+/// left in the file and migrated on a later load.
+pub fn load_app_config() -> Result<AppConfig, String> {
+    let mut config = load_and_migrate()?;
+    config.api_key.clear();
+    config.openai_api_key.clear();
+    Ok(config)
+}
+
+/// The config plus the API key of the active provider, for building the provider.
+/// Never hand this one to the frontend.
+pub fn load_with_secrets() -> Result<AppConfig, String> {
+    let mut config = load_and_migrate()?;
+    match config.ai_provider.as_str() {
+        "claude" if config.api_key.is_empty() => {
+            config.api_key = secrets::get(secrets::CLAUDE_API_KEY)?.unwrap_or_default();
+        }
+        // The bearer token is optional, so a locked store is not fatal here
+        "openai" if config.openai_api_key.is_empty() => match secrets::get(secrets::OPENAI_API_KEY) {
+            Ok(key) => config.openai_api_key = key.unwrap_or_default(),
+            Err(e) => log::warn!("{}", e),
+        },
+        _ => {}
+    }
+    Ok(config)
+}
+
+/// `config` as written to disk, plus plaintext keys that have not been migrated yet.
+#[derive(Serialize)]
+struct ConfigFile<'a> {
+    #[serde(flatten)]
+    config: &'a AppConfig,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    api_key: &'a str,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    openai_api_key: &'a str,
+}
+
+/// Write `config` to `path`, handing the API keys it carries to `store`. Plaintext
+/// keys still in the file (the secret store was locked when it was loaded) are
+/// written back unless `config` replaces them, so saving never loses a key.
+fn save_in(
+    path: &Path,
+    config: &AppConfig,
+    mut store: impl FnMut(&str, &str) -> Result<(), String>,
+) -> Result<(), String> {
+    for (name, value) in plaintext_keys(config) {
+        store(name, value)?;
+    }
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
-    let json = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
-    fs::write(&path, json).map_err(|e| e.to_string())
+    let existing = read_config(path);
+    let old = existing.as_ref().ok();
+    let file = ConfigFile {
+        config,
+        api_key: old.filter(|_| config.api_key.is_empty()).map_or("", |c| c.api_key.as_str()),
+        openai_api_key: old.filter(|_| config.openai_api_key.is_empty()).map_or("", |c| c.openai_api_key.as_str()),
+    };
+    let json = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+    // Keep the last good version as the backup rather than a corrupted file
+    atomic::write_file(path, json.as_bytes(), existing.is_ok())
+}
+
+/// Save `config`. API keys it carries go to the secret store; empty ones leave
+/// the stored key as it is.
+pub fn save_app_config(config: &AppConfig) -> Result<(), String> {
+    save_in(&config_path(), config, secrets::set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::temp_dir;
+
+    #[test]
+    fn test_read_config_reports_corruption() {
+        let dir = temp_dir("config");
+        let path = dir.join("config.json");
+        assert_eq!(read_config(&path).unwrap().theme, default_theme());
+
+        fs::write(&path, r#"{"theme": "dark"}"#).unwrap();
+        assert_eq!(read_config(&path).unwrap().theme, "dark");
+
+        fs::write(&path, r#"{"theme": "da"#).unwrap();
+        let err = read_config(&path).unwrap_err();
+        assert!(err.contains("corrupted"));
+        assert!(!err.contains("previous version"));
+
+        fs::write(atomic::backup_path(&path), "{}").unwrap();
+        assert!(read_config(&path).unwrap_err().contains("config.json.bak"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_keys_out_of_config() {
+        let dir = temp_dir("config-migrate");
+        let path = dir.join("config.json");
+        fs::write(&path, r#"{"api_key": "sk-ant-1", "openai_api_key": "", "theme": "dark"}"#).unwrap();
+        fs::write(atomic::backup_path(&path), r#"{"api_key": "sk-ant-0"}"#).unwrap();
+
+        let mut config = read_config(&path).unwrap();
+        let mut stored = vec![];
+        migrate_keys(&path, &mut config, |name, value| {
+            stored.push((name.to_string(), value.to_string()));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(stored, vec![(secrets::CLAUDE_API_KEY.to_string(), "sk-ant-1".to_string())]);
+        assert!(config.api_key.is_empty());
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(!written.contains("sk-ant") && !written.contains("api_key"));
+        assert_eq!(read_config(&path).unwrap().theme, "dark");
+        assert!(!atomic::backup_path(&path).exists());
+
+        // A store that cannot take the key leaves the file untouched
+        fs::write(&path, r#"{"api_key": "sk-ant-2"}"#).unwrap();
+        let mut config = read_config(&path).unwrap();
+        assert!(migrate_keys(&path, &mut config, |_, _| Err("locked".to_string())).is_err());
+        assert!(fs::read_to_string(&path).unwrap().contains("sk-ant-2"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_keeps_unmigrated_keys_while_store_is_locked() {
+        let dir = temp_dir("config-locked");
+        let path = dir.join("config.json");
+        fs::write(&path, r#"{"api_key": "sk-ant-1", "openai_api_key": "sk-old", "theme": "dark"}"#).unwrap();
+        let locked = |_: &str, _: &str| Err("locked".to_string());
+
+        // What `load_app_config` hands out when migration failed: keys removed
+        let mut config = read_config(&path).unwrap();
+        assert!(migrate_keys(&path, &mut config, locked).is_err());
+        config.api_key.clear();
+        config.openai_api_key.clear();
+        config.theme = "warm-light".to_string();
+        save_in(&path, &config, locked).unwrap();
+        let saved = read_config(&path).unwrap();
+        assert_eq!((saved.api_key.as_str(), saved.theme.as_str()), ("sk-ant-1", "warm-light"));
+        assert_eq!(saved.openai_api_key, "sk-old");
+
+        // A new key cannot be saved into a locked store, and nothing is written
+        config.api_key = "sk-ant-2".to_string();
+        assert!(save_in(&path, &config, locked).is_err());
+        assert_eq!(read_config(&path).unwrap().api_key, "sk-ant-1");
+
+        // Once stored, the new key replaces the old one; the other is still kept
+        save_in(&path, &config, |_, _| Ok(())).unwrap();
+        let saved = read_config(&path).unwrap();
+        assert_eq!((saved.api_key.as_str(), saved.openai_api_key.as_str()), ("", "sk-old"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
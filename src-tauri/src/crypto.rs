@@ -0,0 +1,74 @@
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+pub const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// A 256-bit key derived from a passphrase.
+#[derive(Clone)]
+pub struct SecretKey(Key);
+
+impl SecretKey {
+    /// Stretch `passphrase` with Argon2id (default parameters) and `salt`.
+    pub fn derive(passphrase: &str, salt: &[u8]) -> Result<Self, String> {
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| format!("Key derivation failed: {}", e))?;
+        Ok(SecretKey(key))
+    }
+}
+
+pub fn random_salt() -> [u8; SALT_LEN] {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+/// Encrypt and authenticate `plaintext` under a fresh random nonce. The output is
+/// the nonce followed by the ciphertext.
+pub fn seal(key: &SecretKey, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = ChaCha20Poly1305::new(&key.0)
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| "Encryption failed".to_string())?;
+    let mut out = nonce.to_vec();
+    out.extend(ciphertext);
+    Ok(out)
+}
+
+/// Reverse `seal`. Fails on a wrong key or on any change to the data.
+pub fn open(key: &SecretKey, sealed: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.len() < NONCE_LEN {
+        return Err("Encrypted data is truncated".to_string());
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    ChaCha20Poly1305::new(&key.0)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Wrong passphrase or corrupted data".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let salt = random_salt();
+        let key = SecretKey::derive("correct horse", &salt).unwrap();
+        let sealed = seal(&key, b"secret").unwrap();
+        assert_ne!(&sealed[NONCE_LEN..], b"secret");
+        assert_eq!(open(&key, &sealed).unwrap(), b"secret");
+        // Same plaintext, different nonce
+        assert_ne!(seal(&key, b"secret").unwrap(), sealed);
+
+        let wrong = SecretKey::derive("wrong horse", &salt).unwrap();
+        assert!(open(&wrong, &sealed).is_err());
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(open(&key, &tampered).is_err());
+        assert!(open(&key, &sealed[..4]).is_err());
+    }
+}
@@ -0,0 +1,318 @@
+use serde::Serialize;
+
+// --- Tokenizing ---
+
+/// Unit the diff is computed over.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Granularity {
+    /// Words, whitespace runs and single punctuation marks (space-delimited languages).
+    Word,
+    /// Single characters (Japanese, Chinese, Korean).
+    Char,
+    /// Whole lines, for texts too long to diff at a finer granularity.
+    Line,
+}
+
+impl Granularity {
+    /// Char-level for ja/zh/ko (including region tags like `zh-TW`), word-level otherwise.
+    pub fn for_language(code: &str) -> Self {
+        let primary = code.split(['-', '_']).next().unwrap_or("").to_ascii_lowercase();
+        match primary.as_str() {
+            "ja" | "zh" | "ko" => Granularity::Char,
+            _ => Granularity::Word,
+        }
+    }
+}
+
+/// Scripts written without spaces between words. These are always split per
+/// character, so CJK quoted inside an English entry still diffs sensibly.
+pub(crate) fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'   // Hiragana, Katakana
+        | '\u{3400}'..='\u{4DBF}' // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+        | '\u{AC00}'..='\u{D7AF}' // Hangul syllables
+        | '\u{1100}'..='\u{11FF}' // Hangul Jamo
+        | '\u{3000}'..='\u{303F}' // CJK punctuation
+        | '\u{FF00}'..='\u{FFEF}' // Full-width forms
+    )
+}
+
+#[derive(PartialEq)]
+enum TokenClass {
+    Word,
+    Space,
+    Single,
+}
+
+fn token_class(c: char) -> TokenClass {
+    if is_cjk(c) {
+        TokenClass::Single
+    } else if c.is_whitespace() {
+        TokenClass::Space
+    } else if c.is_alphanumeric() || c == '\'' || c == '’' {
+        TokenClass::Word
+    } else {
+        TokenClass::Single
+    }
+}
+
+/// Split `text` into tokens whose concatenation is `text` again.
+pub fn tokenize(text: &str, granularity: Granularity) -> Vec<&str> {
+    if granularity == Granularity::Line {
+        return text.split_inclusive('\n').collect();
+    }
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut prev: Option<TokenClass> = None;
+    for (i, c) in text.char_indices() {
+        let class = token_class(c);
+        let joins = granularity == Granularity::Word
+            && class != TokenClass::Single
+            && prev.as_ref() == Some(&class);
+        if !joins && i > start {
+            tokens.push(&text[start..i]);
+            start = i;
+        }
+        prev = Some(class);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+// --- Diffing ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HunkKind {
+    Equal,
+    Delete,
+    Insert,
+    Replace,
+}
+
+/// One aligned piece of the diff. Concatenating every `original` gives the original
+/// text and concatenating every `corrected` gives the corrected text.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiffHunk {
+    pub kind: HunkKind,
+    pub original: String,
+    pub corrected: String,
+}
+
+impl DiffHunk {
+    fn new(original: String, corrected: String) -> Self {
+        let kind = match (original.is_empty(), corrected.is_empty()) {
+            (false, true) => HunkKind::Delete,
+            (true, false) => HunkKind::Insert,
+            _ if original == corrected => HunkKind::Equal,
+            _ => HunkKind::Replace,
+        };
+        DiffHunk { kind, original, corrected }
+    }
+}
+
+/// Largest LCS table `diff_tokens` builds, in cells (16 MB). Longer texts are
+/// diffed at a coarser granularity instead.
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+/// Longest common subsequence table over the token slices, read back into hunks.
+/// The quadratic table is only built for what differs after trimming the common
+/// prefix and suffix; None when even that exceeds `MAX_LCS_CELLS`.
+fn diff_tokens(a: &[&str], b: &[&str]) -> Option<Vec<DiffHunk>> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (mid_a, mid_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let (n, m) = (mid_a.len(), mid_b.len());
+    if (n + 1).saturating_mul(m + 1) > MAX_LCS_CELLS {
+        return None;
+    }
+    let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[at(i, j)] = if mid_a[i] == mid_b[j] {
+                lcs[at(i + 1, j + 1)] + 1
+            } else {
+                lcs[at(i + 1, j)].max(lcs[at(i, j + 1)])
+            };
+        }
+    }
+
+    let mut hunks = Vec::new();
+    let mut equal = a[..prefix].concat();
+    let mut removed = String::new();
+    let mut added = String::new();
+    let flush_change = |hunks: &mut Vec<DiffHunk>, removed: &mut String, added: &mut String| {
+        if !removed.is_empty() || !added.is_empty() {
+            hunks.push(DiffHunk::new(std::mem::take(removed), std::mem::take(added)));
+        }
+    };
+
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && mid_a[i] == mid_b[j] {
+            flush_change(&mut hunks, &mut removed, &mut added);
+            equal.push_str(mid_a[i]);
+            i += 1;
+            j += 1;
+            continue;
+        }
+        if !equal.is_empty() {
+            let text = std::mem::take(&mut equal);
+            hunks.push(DiffHunk::new(text.clone(), text));
+        }
+        if j >= m || (i < n && lcs[at(i + 1, j)] >= lcs[at(i, j + 1)]) {
+            removed.push_str(mid_a[i]);
+            i += 1;
+        } else {
+            added.push_str(mid_b[j]);
+            j += 1;
+        }
+    }
+    flush_change(&mut hunks, &mut removed, &mut added);
+    equal.push_str(&a[a.len() - suffix..].concat());
+    if !equal.is_empty() {
+        hunks.push(DiffHunk::new(equal.clone(), equal));
+    }
+    Some(merge_across_whitespace(hunks))
+}
+
+/// Join changes separated only by whitespace, so a rewritten phrase reads as one
+/// replacement instead of alternating word swaps around shared spaces.
+fn merge_across_whitespace(hunks: Vec<DiffHunk>) -> Vec<DiffHunk> {
+    let mut merged: Vec<DiffHunk> = Vec::with_capacity(hunks.len());
+    let mut iter = hunks.into_iter().peekable();
+    while let Some(hunk) = iter.next() {
+        let bridges = hunk.kind == HunkKind::Equal
+            && hunk.original.chars().all(char::is_whitespace)
+            && merged.last().is_some_and(|prev| prev.kind != HunkKind::Equal)
+            && iter.peek().is_some_and(|next| next.kind != HunkKind::Equal);
+        if !bridges {
+            merged.push(hunk);
+            continue;
+        }
+        if let (Some(prev), Some(next)) = (merged.pop(), iter.next()) {
+            merged.push(DiffHunk::new(
+                prev.original + &hunk.original + &next.original,
+                prev.corrected + &hunk.corrected + &next.corrected,
+            ));
+        }
+    }
+    merged
+}
+
+/// Diff `original` against `corrected` at the granularity suited to `language`,
+/// falling back to whole lines, and then to one replacement, for very long texts.
+pub fn diff_text(original: &str, corrected: &str, language: &str) -> Vec<DiffHunk> {
+    [Granularity::for_language(language), Granularity::Line]
+        .into_iter()
+        .find_map(|granularity| diff_tokens(&tokenize(original, granularity), &tokenize(corrected, granularity)))
+        .unwrap_or_else(|| vec![DiffHunk::new(original.to_string(), corrected.to_string())])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(hunks: &[DiffHunk]) -> String {
+        hunks
+            .iter()
+            .map(|h| match h.kind {
+                HunkKind::Equal => h.original.clone(),
+                HunkKind::Delete => format!("[-{}]", h.original),
+                HunkKind::Insert => format!("[+{}]", h.corrected),
+                HunkKind::Replace => format!("[{}→{}]", h.original, h.corrected),
+            })
+            .collect()
+    }
+
+    fn assert_aligned(original: &str, corrected: &str, hunks: &[DiffHunk]) {
+        let a: String = hunks.iter().map(|h| h.original.as_str()).collect();
+        let b: String = hunks.iter().map(|h| h.corrected.as_str()).collect();
+        assert_eq!(a, original);
+        assert_eq!(b, corrected);
+    }
+
+    #[test]
+    fn test_granularity_for_language() {
+        assert_eq!(Granularity::for_language("ja"), Granularity::Char);
+        assert_eq!(Granularity::for_language("zh-TW"), Granularity::Char);
+        assert_eq!(Granularity::for_language("KO"), Granularity::Char);
+        assert_eq!(Granularity::for_language("en"), Granularity::Word);
+        assert_eq!(Granularity::for_language("it"), Granularity::Word);
+    }
+
+    #[test]
+    fn test_tokenize_words_round_trips() {
+        let text = "I don't know, really.  Ok?";
+        let tokens = tokenize(text, Granularity::Word);
+        assert_eq!(tokens, vec!["I", " ", "don't", " ", "know", ",", " ", "really", ".", "  ", "Ok", "?"]);
+        assert_eq!(tokens.concat(), text);
+    }
+
+    #[test]
+    fn test_tokenize_splits_cjk_inside_word_mode() {
+        assert_eq!(tokenize("I ate 寿司", Granularity::Word), vec!["I", " ", "ate", " ", "寿", "司"]);
+    }
+
+    #[test]
+    fn test_word_diff_replaces_whole_words() {
+        let (a, b) = ("Yesterday I goed to the park.", "Yesterday I went to the park.");
+        let hunks = diff_text(a, b, "en");
+        assert_eq!(render(&hunks), "Yesterday I [goed→went] to the park.");
+        assert_aligned(a, b, &hunks);
+    }
+
+    #[test]
+    fn test_word_diff_insert_and_delete() {
+        let (a, b) = ("I have many informations very", "I have a lot of information");
+        let hunks = diff_text(a, b, "en");
+        assert_eq!(render(&hunks), "I have [many informations very→a lot of information]");
+        assert_aligned(a, b, &hunks);
+    }
+
+    #[test]
+    fn test_char_diff_for_japanese() {
+        let (a, b) = ("私は学校を行きました。", "私は学校に行きました。");
+        let hunks = diff_text(a, b, "ja");
+        assert_eq!(render(&hunks), "私は学校[を→に]行きました。");
+        assert_aligned(a, b, &hunks);
+    }
+
+    #[test]
+    fn test_identical_text_is_single_equal_hunk() {
+        let hunks = diff_text("Ciao a tutti", "Ciao a tutti", "it");
+        assert_eq!(hunks, vec![DiffHunk::new("Ciao a tutti".to_string(), "Ciao a tutti".to_string())]);
+        assert!(diff_text("", "", "en").is_empty());
+    }
+
+    #[test]
+    fn test_long_text_falls_back_to_lines() {
+        let a: String = (0..3000).map(|i| if i % 100 == 99 { "あ\n" } else { "あ" }).collect();
+        let b = a.replace("あ\n", "い\n");
+        let hunks = diff_text(&a, &b, "ja");
+        assert_aligned(&a, &b, &hunks);
+        // Every line changed, so the line diff is a single replacement
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].kind, HunkKind::Replace);
+
+        let single_change = a.replacen("あ", "い", 1) + "え";
+        assert_aligned(&a, &single_change, &diff_text(&a, &single_change, "ja"));
+    }
+
+    #[test]
+    fn test_diff_against_empty_text() {
+        let hunks = diff_text("", "新しい", "ja");
+        assert_eq!(render(&hunks), "[+新しい]");
+    }
+}
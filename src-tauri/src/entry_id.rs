@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use chrono::NaiveDate;
+
+const MAX_ID_LEN: usize = 64;
+const MAX_IMAGE_STEM_LEN: usize = 100;
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "avif", "bmp"];
+
+/// An entry id such as "2026-02-24_143052": the entry's date followed by ASCII
+/// letters, digits, `_` or `-`. Ids arrive from the frontend and from file names,
+/// and end up in paths, so they are only ever constructed through validation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct EntryId(String);
+
+impl EntryId {
+    pub fn parse(id: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid entry id: {:?}", id);
+        if id.len() > MAX_ID_LEN || !id.is_char_boundary(10.min(id.len())) {
+            return Err(invalid());
+        }
+        let (date, rest) = id.split_at(10.min(id.len()));
+        // Round-trip so that unpadded dates like "2026-2-4" are rejected too
+        let canonical = NaiveDate::parse_from_str(date, "%Y-%m-%d").map(|d| d.format("%Y-%m-%d").to_string());
+        if canonical.as_deref() != Ok(date)
+            || !rest.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(invalid());
+        }
+        Ok(EntryId(id.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The `YYYY/MM` directory under `base` that holds this entry.
+    pub fn month_dir(&self, base: &Path) -> PathBuf {
+        base.join(&self.0[0..4]).join(&self.0[5..7])
+    }
+}
+
+impl TryFrom<String> for EntryId {
+    type Error = String;
+
+    fn try_from(id: String) -> Result<Self, String> {
+        EntryId::parse(&id)
+    }
+}
+
+impl From<EntryId> for String {
+    fn from(id: EntryId) -> Self {
+        id.0
+    }
+}
+
+impl fmt::Display for EntryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Turn a caller-supplied image file name into a safe one: only the last path
+/// component is kept, anything other than letters, digits, `.`, `_` and `-`
+/// becomes `_`, leading dots are dropped, and the extension must be an image type.
+pub fn sanitize_image_name(filename: &str) -> Result<String, String> {
+    let base = filename.rsplit(['/', '\\']).next().unwrap_or("");
+    let cleaned: String = base
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '_' | '-') { c } else { '_' })
+        .collect();
+    let cleaned = cleaned.trim_start_matches('.');
+
+    let (stem, ext) = cleaned
+        .rsplit_once('.')
+        .ok_or_else(|| format!("Image name has no extension: {:?}", filename))?;
+    let ext = ext.to_ascii_lowercase();
+    if !IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+        return Err(format!("Unsupported image type: {:?}", filename));
+    }
+    let stem: String = stem.chars().take(MAX_IMAGE_STEM_LEN).collect();
+    if stem.is_empty() {
+        return Err(format!("Image name is empty: {:?}", filename));
+    }
+    Ok(format!("{}.{}", stem, ext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_id_accepts_generated_ids() {
+        let id = EntryId::parse("2026-02-24_143052").unwrap();
+        assert_eq!(id.month_dir(Path::new("/base")), PathBuf::from("/base/2026/02"));
+        assert!(EntryId::parse("2026-02-24").is_ok());
+        assert!(EntryId::parse("2026-02-24_143052-2").is_ok());
+    }
+
+    #[test]
+    fn test_entry_id_rejects_hostile_input() {
+        for id in [
+            "",
+            "2026-02-24/../../x",
+            "../../../etc/passwd",
+            "2026-02-24_..",
+            "2026-02-24_/x",
+            "2026-02-24_\\x",
+            "2026-02-24_a b",
+            "2026-13-01_000000",
+            "2026-02-2",
+            "x026-02-24_143052",
+            "2026-02-24_143052\0",
+            "2026-02-2日_1",
+            &format!("2026-02-24_{}", "1".repeat(60)),
+        ] {
+            assert!(EntryId::parse(id).is_err(), "accepted {:?}", id);
+        }
+    }
+
+    #[test]
+    fn test_entry_id_deserializes_with_validation() {
+        let id: EntryId = serde_json::from_str("\"2026-02-24_143052\"").unwrap();
+        assert_eq!(serde_json::to_string(&id).unwrap(), "\"2026-02-24_143052\"");
+        assert!(serde_json::from_str::<EntryId>("\"2026-02-24/../../x\"").is_err());
+    }
+
+    #[test]
+    fn test_sanitize_image_name() {
+        assert_eq!(sanitize_image_name("photo.PNG").unwrap(), "photo.png");
+        assert_eq!(sanitize_image_name("../../.bashrc.png").unwrap(), "bashrc.png");
+        assert_eq!(sanitize_image_name("C:\\Users\\me\\cat pic.jpg").unwrap(), "cat_pic.jpg");
+        assert_eq!(sanitize_image_name("富士山.jpeg").unwrap(), "富士山.jpeg");
+        assert!(sanitize_image_name(".png").is_err());
+        assert!(sanitize_image_name("../../.bashrc").is_err());
+        assert!(sanitize_image_name("evil.svg").is_err());
+        assert!(sanitize_image_name("a/..").is_err());
+        assert!(sanitize_image_name("").is_err());
+        assert_eq!(sanitize_image_name(&format!("{}.gif", "a".repeat(300))).unwrap().len(), 104);
+    }
+}
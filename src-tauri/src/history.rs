@@ -0,0 +1,237 @@
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::NaiveDateTime;
+use crate::config::load_app_config;
+use crate::diff::{self, DiffHunk, HunkKind};
+use crate::entry_id::EntryId;
+use crate::storage::{self, DiaryEntry};
+use crate::vault;
+
+/// Revisions live in `{entries_dir}/.history/{entry id}/{revision}.md`, out of the
+/// `YYYY/MM` tree so they never show up as entries.
+const HISTORY_DIR: &str = ".history";
+/// Revision names are save times, which also sort chronologically.
+const REVISION_FORMAT: &str = "%Y%m%d-%H%M%S%3f";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RevisionInfo {
+    pub revision: String,
+    pub saved_at: String, // ISO 8601
+    pub title: String,
+    pub size: u64,
+}
+
+/// Changes to one part of an entry between two revisions.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SectionDiff {
+    /// "title", "original", or a language code.
+    pub section: String,
+    pub hunks: Vec<DiffHunk>,
+}
+
+fn entry_history_dir(id: &EntryId) -> Result<PathBuf, String> {
+    let dir = storage::entries_dir()?.join(HISTORY_DIR).join(id.as_str());
+    vault::ensure_unlocked(&dir)?;
+    Ok(dir)
+}
+
+fn parse_revision(revision: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(revision, REVISION_FORMAT).ok()
+}
+
+/// Revision names in `dir`, oldest first.
+fn revisions_in(dir: &Path) -> Vec<String> {
+    let Ok(files) = fs::read_dir(dir) else { return vec![] };
+    let mut names: Vec<String> = files
+        .flatten()
+        .filter_map(|f| {
+            let path = f.path();
+            let stem = path.file_stem()?.to_str()?;
+            (path.extension().is_some_and(|ext| ext == "md") && parse_revision(stem).is_some())
+                .then(|| stem.to_string())
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// Store `content` as a new revision in `dir` unless it equals the newest one, then
+/// delete the oldest revisions beyond `retention`.
+fn record_in(dir: &Path, content: &str, retention: usize, now: NaiveDateTime) -> Result<(), String> {
+    let mut revisions = revisions_in(dir);
+    let newest = revisions.last().map(|r| dir.join(format!("{}.md", r)));
+    if newest.is_some_and(|path| vault::read_to_string(&path).is_ok_and(|c| c == content)) {
+        return Ok(());
+    }
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let mut revision = now.format(REVISION_FORMAT).to_string();
+    // Two saves within the same millisecond still get distinct names
+    while revisions.last().is_some_and(|last| *last >= revision) {
+        let next = parse_revision(revisions.last().unwrap()).unwrap() + chrono::Duration::milliseconds(1);
+        revision = next.format(REVISION_FORMAT).to_string();
+    }
+    vault::write(&dir.join(format!("{}.md", revision)), content.as_bytes(), false)?;
+    revisions.push(revision);
+
+    let excess = revisions.len().saturating_sub(retention);
+    for old in &revisions[..excess] {
+        fs::remove_file(dir.join(format!("{}.md", old))).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn list_in(dir: &Path) -> Vec<RevisionInfo> {
+    revisions_in(dir)
+        .into_iter()
+        .rev()
+        .map(|revision| {
+            let path = dir.join(format!("{}.md", revision));
+            let title = storage::read_entry_file(&path).map(|e| e.meta.title).unwrap_or_default();
+            let size = fs::metadata(&path).map_or(0, |m| m.len());
+            let saved_at = parse_revision(&revision)
+                .map(|t| t.format("%Y-%m-%dT%H:%M:%S").to_string())
+                .unwrap_or_default();
+            RevisionInfo { revision, saved_at, title, size }
+        })
+        .collect()
+}
+
+fn read_in(dir: &Path, id: &str, revision: &str) -> Result<DiaryEntry, String> {
+    // Only names we generated, so the revision cannot point outside the directory
+    if parse_revision(revision).is_none() {
+        return Err(format!("Invalid revision: {}", revision));
+    }
+    let path = dir.join(format!("{}.md", revision));
+    if !path.exists() {
+        return Err(format!("Revision not found: {}", revision));
+    }
+    let mut entry = storage::read_entry_file(&path)?;
+    entry.meta.id = id.to_string();
+    Ok(entry)
+}
+
+// --- Public API ---
+
+/// Snapshot the just-saved `content` of entry `id`. Retention comes from the
+/// config; 0 turns history off.
+pub fn record(id: &EntryId, content: &str) -> Result<(), String> {
+    let retention = load_app_config()?.history_retention as usize;
+    if retention == 0 {
+        return Ok(());
+    }
+    record_in(&entry_history_dir(id)?, content, retention, chrono::Local::now().naive_local())
+}
+
+/// Saved revisions of entry `id`, newest first.
+pub fn list(id: &EntryId) -> Result<Vec<RevisionInfo>, String> {
+    Ok(list_in(&entry_history_dir(id)?))
+}
+
+pub fn read(id: &EntryId, revision: &str) -> Result<DiaryEntry, String> {
+    read_in(&entry_history_dir(id)?, id.as_str(), revision)
+}
+
+/// Delete every revision of entry `id`.
+pub fn remove_all(id: &EntryId) -> Result<(), String> {
+    let dir = entry_history_dir(id)?;
+    if dir.exists() {
+        fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Per-section changes from `old` to `new`; unchanged sections are left out.
+pub fn diff_entries(old: &DiaryEntry, new: &DiaryEntry) -> Vec<SectionDiff> {
+    // Title and original are diffed word-wise, which still splits CJK per character
+    let mut sections = vec![
+        ("title".to_string(), "", old.meta.title.as_str(), new.meta.title.as_str()),
+        ("original".to_string(), "", old.original.as_str(), new.original.as_str()),
+    ];
+    let languages: BTreeSet<&String> = old.translations.keys().chain(new.translations.keys()).collect();
+    for lang in languages {
+        let before = old.translations.get(lang).map_or("", |t| t.as_str());
+        let after = new.translations.get(lang).map_or("", |t| t.as_str());
+        sections.push((lang.clone(), lang.as_str(), before, after));
+    }
+
+    sections
+        .into_iter()
+        .map(|(section, language, before, after)| SectionDiff {
+            section,
+            hunks: diff::diff_text(before, after, language),
+        })
+        .filter(|d| d.hunks.iter().any(|h| h.kind != HunkKind::Equal))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::serialize_entry;
+    use crate::storage::test_support::{self, temp_dir};
+
+    fn entry(title: &str, original: &str, translations: Vec<(&str, &str)>) -> DiaryEntry {
+        let mut entry = test_support::entry("2026-05-01_100000");
+        entry.meta.title = title.to_string();
+        entry.meta.mode = "translation".to_string();
+        entry.meta.languages = translations.iter().map(|(l, _)| l.to_string()).collect();
+        entry.original = original.to_string();
+        entry.translations = translations.into_iter().map(|(l, t)| (l.to_string(), t.to_string())).collect();
+        entry
+    }
+
+    fn at(time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S%.3f").unwrap()
+    }
+
+    #[test]
+    fn test_record_skips_duplicates_and_applies_retention() {
+        let dir = temp_dir("history-record");
+        record_in(&dir, "one", 3, at("2026-05-01 10:00:00.000")).unwrap();
+        record_in(&dir, "one", 3, at("2026-05-01 10:01:00.000")).unwrap();
+        assert_eq!(revisions_in(&dir), vec!["20260501-100000000"]);
+
+        record_in(&dir, "two", 3, at("2026-05-01 10:02:00.000")).unwrap();
+        record_in(&dir, "three", 3, at("2026-05-01 10:02:00.000")).unwrap();
+        record_in(&dir, "four", 3, at("2026-05-01 10:03:00.000")).unwrap();
+        assert_eq!(
+            revisions_in(&dir),
+            vec!["20260501-100200000", "20260501-100200001", "20260501-100300000"]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_list_and_read_revisions() {
+        let dir = temp_dir("history-list");
+        let first = entry("Draft", "I go to park.", vec![]);
+        let second = entry("Park", "I went to the park.", vec![]);
+        record_in(&dir, &serialize_entry(&first), 10, at("2026-05-01 10:00:00.000")).unwrap();
+        record_in(&dir, &serialize_entry(&second), 10, at("2026-05-01 11:30:00.250")).unwrap();
+
+        let list = list_in(&dir);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].title, "Park");
+        assert_eq!(list[0].saved_at, "2026-05-01T11:30:00");
+        assert_eq!(list[1].revision, "20260501-100000000");
+
+        let restored = read_in(&dir, "2026-05-01_100000", &list[1].revision).unwrap();
+        assert_eq!(restored.original, "I go to park.");
+        assert!(read_in(&dir, "2026-05-01_100000", "../../2026/05/2026-05-01_100000").is_err());
+        assert!(read_in(&dir, "2026-05-01_100000", "20990101-000000000").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_diff_entries_reports_changed_sections() {
+        let old = entry("Day", "I go to park.", vec![("it", "Vado al parco."), ("ja", "公園に行く。")]);
+        let new = entry("Day", "I went to the park.", vec![("it", "Vado al parco."), ("en", "I went.")]);
+        let diffs = diff_entries(&old, &new);
+        let sections: Vec<&str> = diffs.iter().map(|d| d.section.as_str()).collect();
+        assert_eq!(sections, vec!["original", "en", "ja"]);
+        assert!(diffs[2].hunks.iter().all(|h| h.kind == HunkKind::Delete));
+        assert!(diff_entries(&old, &old).is_empty());
+    }
+}
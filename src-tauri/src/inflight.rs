@@ -0,0 +1,109 @@
+use futures::future::{AbortHandle, Abortable};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use crate::provider::AiError;
+
+/// AI requests that can still be aborted, keyed by the frontend-supplied request id.
+#[derive(Default)]
+pub struct InFlightRequests {
+    handles: Mutex<HashMap<String, AbortHandle>>,
+}
+
+/// Unregisters the request when it finishes, is cancelled or gets dropped.
+struct Registration<'a> {
+    requests: &'a InFlightRequests,
+    id: &'a str,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut handles) = self.requests.handles.lock() {
+            handles.remove(self.id);
+        }
+    }
+}
+
+impl InFlightRequests {
+    /// Run `fut` under `request_id` so `cancel` can abort it. Dropping the future drops
+    /// every HTTP request inside it, including each parallel translation.
+    /// Requests without an id cannot be cancelled and simply run to completion. An id
+    /// that is already running is refused, so each id cancels exactly one request.
+    pub async fn run<F: Future>(&self, request_id: Option<&str>, fut: F) -> Result<F::Output, AiError> {
+        let Some(id) = request_id else {
+            return Ok(fut.await);
+        };
+        let (handle, registration) = AbortHandle::new_pair();
+        if let Ok(mut handles) = self.handles.lock() {
+            if handles.contains_key(id) {
+                return Err(AiError::Refused(format!("Request {} is already running", id)));
+            }
+            handles.insert(id.to_string(), handle);
+        }
+        let _registration = Registration { requests: self, id };
+        Abortable::new(fut, registration)
+            .await
+            .map_err(|_| AiError::Cancelled)
+    }
+
+    /// Abort the request with this id. Returns false if nothing with that id is running.
+    pub fn cancel(&self, request_id: &str) -> bool {
+        let handle = match self.handles.lock() {
+            Ok(mut handles) => handles.remove(request_id),
+            Err(_) => None,
+        };
+        match handle {
+            Some(handle) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_without_id_completes() {
+        let requests = InFlightRequests::default();
+        assert_eq!(requests.run(None, async { 42 }).await.unwrap(), 42);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_aborts_pending_request() {
+        let requests = InFlightRequests::default();
+        let pending = requests.run(Some("req-1"), futures::future::pending::<()>());
+        let cancel = async {
+            tokio::task::yield_now().await;
+            assert!(requests.cancel("req-1"));
+        };
+        let (result, _) = tokio::join!(pending, cancel);
+        assert_eq!(result.unwrap_err(), AiError::Cancelled);
+        assert!(!requests.cancel("req-1"));
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_id_is_refused() {
+        let requests = InFlightRequests::default();
+        let first = requests.run(Some("req-3"), futures::future::pending::<()>());
+        let second = async {
+            tokio::task::yield_now().await;
+            let duplicate = requests.run(Some("req-3"), async {}).await;
+            assert!(matches!(duplicate, Err(AiError::Refused(_))));
+            // The refused call must not unregister the running one
+            assert!(requests.cancel("req-3"));
+        };
+        let (result, _) = tokio::join!(first, second);
+        assert_eq!(result.unwrap_err(), AiError::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_finished_request_is_unregistered() {
+        let requests = InFlightRequests::default();
+        requests.run(Some("req-2"), async {}).await.unwrap();
+        assert!(!requests.cancel("req-2"));
+    }
+}
@@ -2,8 +2,26 @@ mod commands;
 mod storage;
 mod claude;
 mod config;
+mod provider;
+mod inflight;
+mod diff;
+mod mistakes;
+mod vocabulary;
+mod reviews;
+mod anki;
+mod search;
+mod atomic;
+mod history;
+mod trash;
+mod entry_id;
+mod crypto;
+mod secrets;
+mod vault;
+mod audit;
+mod redact;
 
 use commands::*;
+use inflight::InFlightRequests;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -11,6 +29,7 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
+        .manage(InFlightRequests::default())
         .setup(|app| {
             if cfg!(debug_assertions) {
                 app.handle().plugin(
@@ -19,20 +38,56 @@ pub fn run() {
                         .build(),
                 )?;
             }
+            if let Err(e) = trash::purge_expired() {
+                log::warn!("Failed to purge expired trash: {}", e);
+            }
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             list_entries,
             search_entries,
+            rebuild_search_index,
             read_entry,
             save_entry,
             save_image,
+            read_image,
             delete_entry,
+            list_trash,
+            restore_entry,
+            empty_trash,
+            list_revisions,
+            diff_revisions,
+            restore_revision,
             create_entry_id,
             correct_text,
             translate_text,
+            diff_text,
+            get_mistake_stats,
+            extract_vocabulary,
+            add_vocabulary,
+            list_vocabulary,
+            update_vocabulary,
+            tag_vocabulary,
+            delete_vocabulary,
+            get_due_reviews,
+            grade_review,
+            export_anki,
+            cancel_ai_request,
+            get_ai_provider,
+            get_audit_log,
+            check_ollama,
+            pull_ollama_model,
             load_config,
             save_config,
+            get_secret_status,
+            init_secrets,
+            unlock_secrets,
+            set_api_key,
+            get_vault_status,
+            unlock_vault,
+            lock_vault,
+            enable_vault,
+            disable_vault,
             get_entries_dir,
             print_page,
         ])
@@ -0,0 +1,285 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use crate::claude::CorrectionEdit;
+use crate::storage::DiaryEntry;
+
+// --- Taxonomy ---
+
+/// Kind of mistake a correction edit fixes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", from = "String")]
+pub enum MistakeCategory {
+    Particles,
+    VerbConjugation,
+    Articles,
+    Tense,
+    WordChoice,
+    Spelling,
+    Punctuation,
+    #[default]
+    Other,
+}
+
+impl MistakeCategory {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MistakeCategory::Particles => "particles",
+            MistakeCategory::VerbConjugation => "verb_conjugation",
+            MistakeCategory::Articles => "articles",
+            MistakeCategory::Tense => "tense",
+            MistakeCategory::WordChoice => "word_choice",
+            MistakeCategory::Spelling => "spelling",
+            MistakeCategory::Punctuation => "punctuation",
+            MistakeCategory::Other => "other",
+        }
+    }
+
+    /// Map a model- or user-supplied label onto the taxonomy. Unknown labels
+    /// (including the free-form ones stored before the taxonomy existed) become Other.
+    pub fn parse(label: &str) -> Self {
+        let label = label.trim().to_lowercase().replace([' ', '-'], "_");
+        match label.as_str() {
+            "particles" | "particle" => MistakeCategory::Particles,
+            "verb_conjugation" | "conjugation" | "verb_form" | "inflection" => MistakeCategory::VerbConjugation,
+            "articles" | "article" | "determiner" => MistakeCategory::Articles,
+            "tense" | "verb_tense" | "aspect" => MistakeCategory::Tense,
+            "word_choice" | "vocabulary" | "naturalness" | "wording" | "collocation" => MistakeCategory::WordChoice,
+            "spelling" | "typo" | "orthography" => MistakeCategory::Spelling,
+            "punctuation" => MistakeCategory::Punctuation,
+            _ => MistakeCategory::Other,
+        }
+    }
+
+    /// Categories that make sense for a language code. Other is always included.
+    pub fn for_language(code: &str) -> &'static [MistakeCategory] {
+        use MistakeCategory::*;
+        let primary = code.split(['-', '_']).next().unwrap_or("").to_ascii_lowercase();
+        match primary.as_str() {
+            "ja" | "ko" => &[Particles, VerbConjugation, Tense, WordChoice, Spelling, Punctuation, Other],
+            "zh" => &[Particles, WordChoice, Spelling, Punctuation, Other],
+            _ => &[Articles, VerbConjugation, Tense, WordChoice, Spelling, Punctuation, Other],
+        }
+    }
+}
+
+impl From<String> for MistakeCategory {
+    fn from(label: String) -> Self {
+        MistakeCategory::parse(&label)
+    }
+}
+
+/// Fold categories that do not apply to `language` (e.g. articles in Japanese) into Other.
+pub fn normalize_edits(edits: &mut [CorrectionEdit], language: &str) {
+    let allowed = MistakeCategory::for_language(language);
+    for edit in edits {
+        if !allowed.contains(&edit.category) {
+            edit.category = MistakeCategory::Other;
+        }
+    }
+}
+
+// --- Statistics ---
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CategoryCount {
+    pub category: MistakeCategory,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MonthlyMistakes {
+    pub month: String, // "2026-02"
+    /// Corrected entries that month, including those without mistakes, to turn
+    /// counts into a per-entry rate.
+    pub entries: usize,
+    pub total: usize,
+    pub categories: Vec<CategoryCount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LanguageMistakes {
+    pub language: String,
+    pub entries: usize,
+    pub total: usize,
+    /// Most frequent first.
+    pub top_categories: Vec<CategoryCount>,
+    /// Oldest month first.
+    pub monthly: Vec<MonthlyMistakes>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MistakeStats {
+    pub languages: Vec<LanguageMistakes>,
+}
+
+#[derive(Default)]
+struct Tally {
+    entries: usize,
+    counts: HashMap<MistakeCategory, usize>,
+}
+
+impl Tally {
+    fn add(&mut self, edits: &[CorrectionEdit]) {
+        self.entries += 1;
+        for edit in edits {
+            *self.counts.entry(edit.category).or_default() += 1;
+        }
+    }
+
+    fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    fn sorted(&self) -> Vec<CategoryCount> {
+        let mut counts: Vec<CategoryCount> = self
+            .counts
+            .iter()
+            .map(|(&category, &count)| CategoryCount { category, count })
+            .collect();
+        counts.sort_by(|a, b| b.count.cmp(&a.count).then(a.category.cmp(&b.category)));
+        counts
+    }
+}
+
+/// Aggregate the saved edit lists of `entries`, optionally for one language only.
+/// An empty list is a correction that found nothing, and still counts as an entry.
+pub fn compute_stats(entries: &[DiaryEntry], language: Option<&str>) -> MistakeStats {
+    // language -> (overall, month -> tally)
+    let mut by_language: BTreeMap<&str, (Tally, BTreeMap<&str, Tally>)> = BTreeMap::new();
+    for entry in entries {
+        let month = entry.meta.date.get(..7).unwrap_or(&entry.meta.date);
+        for (lang, edits) in &entry.edits {
+            if language.is_some_and(|l| l != lang) {
+                continue;
+            }
+            let (overall, monthly) = by_language.entry(lang).or_default();
+            overall.add(edits);
+            monthly.entry(month).or_default().add(edits);
+        }
+    }
+
+    let languages = by_language
+        .into_iter()
+        .map(|(lang, (overall, monthly))| LanguageMistakes {
+            language: lang.to_string(),
+            entries: overall.entries,
+            total: overall.total(),
+            top_categories: overall.sorted(),
+            monthly: monthly
+                .into_iter()
+                .map(|(month, tally)| MonthlyMistakes {
+                    month: month.to_string(),
+                    entries: tally.entries,
+                    total: tally.total(),
+                    categories: tally.sorted(),
+                })
+                .collect(),
+        })
+        .collect();
+    MistakeStats { languages }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support;
+
+    fn edit(category: MistakeCategory) -> CorrectionEdit {
+        CorrectionEdit {
+            original: "x".to_string(),
+            replacement: "y".to_string(),
+            category,
+            reason: String::new(),
+        }
+    }
+
+    fn entry(date: &str, edits: Vec<(&str, Vec<MistakeCategory>)>) -> DiaryEntry {
+        let mut entry = test_support::entry(&format!("{}_120000", date));
+        entry.meta.languages = edits.iter().map(|(l, _)| l.to_string()).collect();
+        entry.edits = edits
+            .into_iter()
+            .map(|(l, cats)| (l.to_string(), cats.into_iter().map(edit).collect()))
+            .collect();
+        entry
+    }
+
+    #[test]
+    fn test_parse_category_labels() {
+        assert_eq!(MistakeCategory::parse("Verb conjugation"), MistakeCategory::VerbConjugation);
+        assert_eq!(MistakeCategory::parse("particle"), MistakeCategory::Particles);
+        assert_eq!(MistakeCategory::parse("word-choice"), MistakeCategory::WordChoice);
+        assert_eq!(MistakeCategory::parse("naturalness"), MistakeCategory::WordChoice);
+        assert_eq!(MistakeCategory::parse("grammar"), MistakeCategory::Other);
+    }
+
+    #[test]
+    fn test_category_deserializes_leniently() {
+        let edit: CorrectionEdit = serde_json::from_str(r#"{"original":"a","category":"Typo"}"#).unwrap();
+        assert_eq!(edit.category, MistakeCategory::Spelling);
+        assert_eq!(serde_json::to_string(&MistakeCategory::VerbConjugation).unwrap(), "\"verb_conjugation\"");
+    }
+
+    #[test]
+    fn test_normalize_edits_per_language() {
+        let mut edits = vec![edit(MistakeCategory::Articles), edit(MistakeCategory::Particles)];
+        normalize_edits(&mut edits, "ja");
+        assert_eq!(edits[0].category, MistakeCategory::Other);
+        assert_eq!(edits[1].category, MistakeCategory::Particles);
+
+        let mut edits = vec![edit(MistakeCategory::Articles), edit(MistakeCategory::Particles)];
+        normalize_edits(&mut edits, "it");
+        assert_eq!(edits[0].category, MistakeCategory::Articles);
+        assert_eq!(edits[1].category, MistakeCategory::Other);
+    }
+
+    #[test]
+    fn test_compute_stats_top_categories_and_months() {
+        use MistakeCategory::*;
+        let entries = vec![
+            entry("2026-01-10", vec![("ja", vec![Particles, Particles, Spelling])]),
+            entry("2026-01-20", vec![("ja", vec![Particles])]),
+            entry("2026-02-03", vec![("ja", vec![Tense]), ("en", vec![Articles])]),
+            entry("2026-02-04", vec![("ja", vec![])]),
+        ];
+        let stats = compute_stats(&entries, None);
+        assert_eq!(stats.languages.len(), 2);
+        assert_eq!(stats.languages[0].language, "en");
+
+        let ja = &stats.languages[1];
+        assert_eq!(ja.entries, 4);
+        assert_eq!(ja.total, 5);
+        assert_eq!(ja.top_categories[0], CategoryCount { category: Particles, count: 3 });
+        assert_eq!(ja.monthly.len(), 2);
+        assert_eq!(ja.monthly[0].month, "2026-01");
+        assert_eq!(ja.monthly[0].entries, 2);
+        assert_eq!(ja.monthly[0].total, 4);
+        assert_eq!(ja.monthly[1].entries, 2);
+        assert_eq!(ja.monthly[1].categories, vec![CategoryCount { category: Tense, count: 1 }]);
+    }
+
+    #[test]
+    fn test_compute_stats_counts_clean_months() {
+        let entries = vec![
+            entry("2026-04-01", vec![("it", vec![MistakeCategory::Articles, MistakeCategory::Tense])]),
+            entry("2026-05-01", vec![("it", vec![])]),
+            entry("2026-05-02", vec![("it", vec![])]),
+        ];
+        let it = &compute_stats(&entries, None).languages[0];
+        assert_eq!((it.entries, it.total), (3, 2));
+        assert_eq!(it.monthly.len(), 2);
+        assert_eq!(it.monthly[1].month, "2026-05");
+        assert_eq!((it.monthly[1].entries, it.monthly[1].total), (2, 0));
+        assert!(it.monthly[1].categories.is_empty());
+    }
+
+    #[test]
+    fn test_compute_stats_language_filter() {
+        let entries = vec![entry(
+            "2026-03-01",
+            vec![("ja", vec![MistakeCategory::Particles]), ("en", vec![MistakeCategory::Articles])],
+        )];
+        let stats = compute_stats(&entries, Some("en"));
+        assert_eq!(stats.languages.len(), 1);
+        assert_eq!(stats.languages[0].top_categories[0].category, MistakeCategory::Articles);
+    }
+}
@@ -0,0 +1,638 @@
+use futures::future::BoxFuture;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use crate::claude::{self, OnDelta};
+use crate::config::{AppConfig, TaskOverrides};
+
+/// Why an AI request failed. Serialized to the frontend as
+/// `{ code, message, retry_after_secs }` so the UI can react to the `code`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AiError {
+    /// Provider missing, unknown, or lacking a required setting.
+    NotConfigured(String),
+    /// API key rejected (401/403).
+    Auth(String),
+    RateLimited { message: String, retry_after: Option<Duration> },
+    /// Provider temporarily over capacity (503, Anthropic 529).
+    Overloaded { message: String, retry_after: Option<Duration> },
+    Timeout(String),
+    /// Nothing is listening at the configured URL.
+    ConnectionRefused(String),
+    ModelNotFound(String),
+    /// Rejected input, e.g. the text exceeds the model's context window.
+    BadRequest(String),
+    Server(String),
+    Network(String),
+    /// The response could not be parsed or was empty.
+    MalformedOutput(String),
+    /// Stopped before sending, e.g. a private entry with a remote provider.
+    Refused(String),
+    Cancelled,
+}
+
+impl AiError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AiError::NotConfigured(_) => "not_configured",
+            AiError::Auth(_) => "auth",
+            AiError::RateLimited { .. } => "rate_limited",
+            AiError::Overloaded { .. } => "overloaded",
+            AiError::Timeout(_) => "timeout",
+            AiError::ConnectionRefused(_) => "connection_refused",
+            AiError::ModelNotFound(_) => "model_not_found",
+            AiError::BadRequest(_) => "bad_request",
+            AiError::Server(_) => "server_error",
+            AiError::Network(_) => "network",
+            AiError::MalformedOutput(_) => "malformed_output",
+            AiError::Refused(_) => "refused",
+            AiError::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            AiError::RateLimited { retry_after, .. } | AiError::Overloaded { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Worth retrying automatically. Timeouts are excluded: a local model that
+    /// needed the full timeout once will almost certainly need it again.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            AiError::RateLimited { .. } | AiError::Overloaded { .. } | AiError::Server(_) | AiError::Network(_)
+        )
+    }
+}
+
+impl fmt::Display for AiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AiError::NotConfigured(m)
+            | AiError::Auth(m)
+            | AiError::Timeout(m)
+            | AiError::ConnectionRefused(m)
+            | AiError::ModelNotFound(m)
+            | AiError::BadRequest(m)
+            | AiError::Server(m)
+            | AiError::Network(m)
+            | AiError::MalformedOutput(m)
+            | AiError::Refused(m) => f.write_str(m),
+            AiError::RateLimited { message, .. } | AiError::Overloaded { message, .. } => f.write_str(message),
+            AiError::Cancelled => f.write_str("Request cancelled"),
+        }
+    }
+}
+
+impl Serialize for AiError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AiError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("retry_after_secs", &self.retry_after().map(|d| d.as_secs_f64()))?;
+        state.end()
+    }
+}
+
+impl From<AiError> for String {
+    fn from(e: AiError) -> Self {
+        e.to_string()
+    }
+}
+
+const MAX_RETRIES: u32 = 3;
+const BASE_RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Waits longer than this are left to the user rather than retried silently.
+/// Server-provided `retry-after` values are capped at it.
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// How long to wait before retry number `attempt + 1`, or None to give up.
+/// A server-provided `retry-after` wins over exponential backoff.
+fn retry_delay(attempt: u32, err: &AiError) -> Option<Duration> {
+    if attempt >= MAX_RETRIES || !err.is_transient() {
+        return None;
+    }
+    let delay = err.retry_after().unwrap_or(BASE_RETRY_DELAY * 2u32.pow(attempt));
+    (delay <= MAX_RETRY_DELAY).then_some(delay)
+}
+
+/// What the text is being sent for; selects the per-task overrides in `AppConfig`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AiTask {
+    Correction,
+    Translation,
+    Vocabulary,
+}
+
+/// Sampling and transport limits for one request, after applying task overrides.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerationOptions {
+    pub max_tokens: u32,
+    pub temperature: Option<f32>,
+    pub timeout: Duration,
+}
+
+fn task_overrides(config: &AppConfig, task: AiTask) -> &TaskOverrides {
+    match task {
+        AiTask::Correction => &config.correction_overrides,
+        // Vocabulary extraction reads translated text, so it shares the translation settings
+        AiTask::Translation | AiTask::Vocabulary => &config.translation_overrides,
+    }
+}
+
+fn generation_options(config: &AppConfig, task: AiTask) -> GenerationOptions {
+    let overrides = task_overrides(config, task);
+    GenerationOptions {
+        max_tokens: overrides.max_tokens.unwrap_or(config.max_tokens),
+        temperature: overrides.temperature.or(config.temperature),
+        timeout: Duration::from_secs(overrides.timeout_secs.unwrap_or(config.timeout_secs)),
+    }
+}
+
+/// The task's model override, or the provider's configured model.
+fn model_for(config: &AppConfig, task: AiTask, provider_model: &str) -> String {
+    task_overrides(config, task)
+        .model
+        .clone()
+        .filter(|m| !m.trim().is_empty())
+        .unwrap_or_else(|| provider_model.to_string())
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ChatMessage {
+    pub role: String, // "system", "user" or "assistant"
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn new(role: &str, content: &str) -> Self {
+        Self { role: role.to_string(), content: content.to_string() }
+    }
+}
+
+/// What a backend can do, so callers can decide without matching on ids.
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct ProviderCapabilities {
+    /// Text leaves the machine when this provider is used.
+    pub remote: bool,
+    pub requires_api_key: bool,
+    /// `on_delta` receives text incrementally rather than all at once.
+    pub streaming: bool,
+    /// `json_schema` is enforced by the backend (tool use, JSON mode) rather than only by the prompt.
+    pub structured_output: bool,
+}
+
+/// Per-request options for `AiProvider::chat`.
+#[derive(Clone, Copy, Default)]
+pub struct ChatOptions<'a> {
+    /// Ask for a JSON reply matching this schema; the reply text is then the JSON document.
+    pub json_schema: Option<&'a serde_json::Value>,
+    /// Stream the reply, forwarding text to this callback as it arrives.
+    pub on_delta: Option<OnDelta<'a>>,
+}
+
+pub trait AiProvider: Send + Sync {
+    fn id(&self) -> &'static str;
+
+    fn capabilities(&self) -> ProviderCapabilities;
+
+    /// Send a full conversation and return the assistant's reply.
+    fn chat<'a>(
+        &'a self,
+        messages: &'a [ChatMessage],
+        options: ChatOptions<'a>,
+    ) -> BoxFuture<'a, Result<String, AiError>>;
+
+    /// Single-turn helper: one system prompt plus one user message. Transient
+    /// failures are retried with exponential backoff, unless part of the reply
+    /// was already streamed.
+    fn complete<'a>(
+        &'a self,
+        system: &'a str,
+        user_text: &'a str,
+        options: ChatOptions<'a>,
+    ) -> BoxFuture<'a, Result<String, AiError>> {
+        Box::pin(async move {
+            let messages = [ChatMessage::new("system", system), ChatMessage::new("user", user_text)];
+            let streamed = AtomicBool::new(false);
+            let forward = |delta: &str| {
+                streamed.store(true, Ordering::Relaxed);
+                if let Some(on_delta) = options.on_delta {
+                    on_delta(delta);
+                }
+            };
+            let options = ChatOptions {
+                on_delta: options.on_delta.map(|_| &forward as OnDelta),
+                ..options
+            };
+            let mut attempt = 0;
+            loop {
+                let err = match self.chat(&messages, options).await {
+                    Err(err) if !streamed.load(Ordering::Relaxed) => err,
+                    other => return other,
+                };
+                let Some(delay) = retry_delay(attempt, &err) else {
+                    return Err(err);
+                };
+                log::warn!("{} request failed ({}), retrying in {:?}", self.id(), err, delay);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        })
+    }
+}
+
+/// Split a conversation into the Anthropic-style `system` string and the remaining turns.
+fn split_system(messages: &[ChatMessage]) -> (String, Vec<ChatMessage>) {
+    let system = messages
+        .iter()
+        .filter(|m| m.role == "system")
+        .map(|m| m.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let rest = messages.iter().filter(|m| m.role != "system").cloned().collect();
+    (system, rest)
+}
+
+// --- Claude ---
+
+pub struct ClaudeProvider {
+    api_key: String,
+    model: String,
+    options: GenerationOptions,
+}
+
+impl ClaudeProvider {
+    fn from_config(config: &AppConfig, task: AiTask) -> Result<Box<dyn AiProvider>, AiError> {
+        if config.api_key.is_empty() {
+            return Err(AiError::NotConfigured(
+                "API key not configured. Please set it in Settings.".to_string(),
+            ));
+        }
+        Ok(Box::new(Self {
+            api_key: config.api_key.clone(),
+            model: model_for(config, task, &config.claude_model),
+            options: generation_options(config, task),
+        }))
+    }
+}
+
+impl AiProvider for ClaudeProvider {
+    fn id(&self) -> &'static str {
+        "claude"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities { remote: true, requires_api_key: true, streaming: true, structured_output: true }
+    }
+
+    fn chat<'a>(
+        &'a self,
+        messages: &'a [ChatMessage],
+        options: ChatOptions<'a>,
+    ) -> BoxFuture<'a, Result<String, AiError>> {
+        Box::pin(async move {
+            let (system, turns) = split_system(messages);
+            claude::call_claude(
+                &self.api_key,
+                &self.model,
+                &system,
+                &turns,
+                &self.options,
+                options.json_schema,
+                options.on_delta,
+            )
+            .await
+        })
+    }
+}
+
+// --- Ollama ---
+
+pub struct OllamaProvider {
+    base_url: String,
+    model: String,
+    options: GenerationOptions,
+}
+
+impl OllamaProvider {
+    fn from_config(config: &AppConfig, task: AiTask) -> Result<Box<dyn AiProvider>, AiError> {
+        Ok(Box::new(Self {
+            base_url: config.ollama_url.clone(),
+            model: model_for(config, task, &config.ollama_model),
+            options: generation_options(config, task),
+        }))
+    }
+}
+
+impl AiProvider for OllamaProvider {
+    fn id(&self) -> &'static str {
+        "ollama"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            remote: !is_local_url(&self.base_url),
+            requires_api_key: false,
+            streaming: true,
+            structured_output: true,
+        }
+    }
+
+    fn chat<'a>(
+        &'a self,
+        messages: &'a [ChatMessage],
+        options: ChatOptions<'a>,
+    ) -> BoxFuture<'a, Result<String, AiError>> {
+        Box::pin(claude::call_ollama(
+            &self.base_url,
+            &self.model,
+            messages,
+            &self.options,
+            options.json_schema.is_some(),
+            options.on_delta,
+        ))
+    }
+}
+
+// --- OpenAI-compatible ---
+
+pub struct OpenAiProvider {
+    base_url: String,
+    api_key: String,
+    model: String,
+    options: GenerationOptions,
+}
+
+impl OpenAiProvider {
+    fn from_config(config: &AppConfig, task: AiTask) -> Result<Box<dyn AiProvider>, AiError> {
+        if config.openai_url.trim().is_empty() {
+            return Err(AiError::NotConfigured(
+                "OpenAI-compatible server URL not configured. Please set it in Settings.".to_string(),
+            ));
+        }
+        Ok(Box::new(Self {
+            base_url: config.openai_url.clone(),
+            api_key: config.openai_api_key.clone(),
+            model: model_for(config, task, &config.openai_model),
+            options: generation_options(config, task),
+        }))
+    }
+}
+
+impl AiProvider for OpenAiProvider {
+    fn id(&self) -> &'static str {
+        "openai"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            remote: !is_local_url(&self.base_url),
+            requires_api_key: false,
+            streaming: true,
+            structured_output: true,
+        }
+    }
+
+    fn chat<'a>(
+        &'a self,
+        messages: &'a [ChatMessage],
+        options: ChatOptions<'a>,
+    ) -> BoxFuture<'a, Result<String, AiError>> {
+        Box::pin(claude::call_openai(
+            &self.base_url,
+            &self.api_key,
+            &self.model,
+            messages,
+            &self.options,
+            options.json_schema,
+            options.on_delta,
+        ))
+    }
+}
+
+/// True when the URL points at this machine (loopback host), so text never leaves it.
+fn is_local_url(url: &str) -> bool {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = without_scheme.split(['/', '?', '#']).next().unwrap_or("");
+    let host = if let Some(bracketed) = authority.strip_prefix('[') {
+        bracketed.split(']').next().unwrap_or("")
+    } else {
+        authority.split(':').next().unwrap_or("")
+    };
+    matches!(host.to_lowercase().as_str(), "localhost" | "::1") || host.starts_with("127.")
+}
+
+// --- Registry ---
+
+type ProviderFactory = fn(&AppConfig, AiTask) -> Result<Box<dyn AiProvider>, AiError>;
+
+/// Every supported backend, keyed by the id stored in `AppConfig.ai_provider`.
+/// New backends only need an entry here.
+const REGISTRY: &[(&str, ProviderFactory)] = &[
+    ("claude", ClaudeProvider::from_config),
+    ("ollama", OllamaProvider::from_config),
+    ("openai", OpenAiProvider::from_config),
+];
+
+pub fn provider_ids() -> Vec<&'static str> {
+    REGISTRY.iter().map(|(id, _)| *id).collect()
+}
+
+/// Build the provider selected in the config, set up with the model and generation
+/// options for `task`, or explain why it can't be used.
+pub fn resolve(config: &AppConfig, task: AiTask) -> Result<Box<dyn AiProvider>, AiError> {
+    let id = config.ai_provider.as_str();
+    let (_, factory) = REGISTRY
+        .iter()
+        .find(|(key, _)| *key == id)
+        .ok_or_else(|| {
+            AiError::NotConfigured(format!(
+                "Unknown AI provider \"{}\". Supported providers: {}",
+                id,
+                provider_ids().join(", ")
+            ))
+        })?;
+    factory(config, task)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve_for(config: &AppConfig) -> Result<Box<dyn AiProvider>, AiError> {
+        resolve(config, AiTask::Correction)
+    }
+
+    fn config_with(provider: &str, api_key: &str) -> AppConfig {
+        AppConfig {
+            ai_provider: provider.to_string(),
+            api_key: api_key.to_string(),
+            ..AppConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_resolve_known_providers() {
+        assert_eq!(resolve_for(&config_with("ollama", "")).unwrap().id(), "ollama");
+        assert_eq!(resolve_for(&config_with("claude", "sk-test")).unwrap().id(), "claude");
+    }
+
+    #[test]
+    fn test_resolve_unknown_provider_is_an_error() {
+        let err = resolve_for(&config_with("gpt", "")).err().unwrap().to_string();
+        assert!(err.contains("Unknown AI provider \"gpt\""));
+        assert!(err.contains("ollama"));
+    }
+
+    #[test]
+    fn test_resolve_claude_requires_api_key() {
+        let err = resolve_for(&config_with("claude", "")).err().unwrap();
+        assert_eq!(err.code(), "not_configured");
+        assert!(err.to_string().contains("API key not configured"));
+    }
+
+    #[test]
+    fn test_openai_provider_remote_depends_on_host() {
+        let mut config = config_with("openai", "");
+        config.openai_url = "http://localhost:1234/v1".to_string();
+        assert!(!resolve_for(&config).unwrap().capabilities().remote);
+        config.openai_url = "https://api.example.com/v1".to_string();
+        assert!(resolve_for(&config).unwrap().capabilities().remote);
+    }
+
+    #[test]
+    fn test_ollama_provider_remote_depends_on_host() {
+        let mut config = config_with("ollama", "");
+        assert!(!resolve_for(&config).unwrap().capabilities().remote);
+        config.ollama_url = "http://192.168.1.20:11434".to_string();
+        assert!(resolve_for(&config).unwrap().capabilities().remote);
+    }
+
+    #[test]
+    fn test_is_local_url() {
+        assert!(is_local_url("http://localhost:8080/v1"));
+        assert!(is_local_url("http://127.0.0.1:8000/v1"));
+        assert!(is_local_url("http://[::1]:8080/v1"));
+        assert!(!is_local_url("https://localhost.example.com/v1"));
+        assert!(!is_local_url("http://192.168.1.20:8080/v1"));
+    }
+
+    #[test]
+    fn test_generation_options_apply_task_overrides() {
+        let mut config = config_with("ollama", "");
+        config.temperature = Some(0.7);
+        config.translation_overrides.max_tokens = Some(1024);
+        config.translation_overrides.timeout_secs = Some(30);
+        config.correction_overrides.temperature = Some(0.2);
+
+        let translation = generation_options(&config, AiTask::Translation);
+        assert_eq!(translation.max_tokens, 1024);
+        assert_eq!(translation.temperature, Some(0.7));
+        assert_eq!(translation.timeout, Duration::from_secs(30));
+
+        let correction = generation_options(&config, AiTask::Correction);
+        assert_eq!(correction.max_tokens, 4096);
+        assert_eq!(correction.temperature, Some(0.2));
+        assert_eq!(correction.timeout, Duration::from_secs(120));
+    }
+
+    #[test]
+    fn test_model_override_per_task() {
+        let mut config = config_with("claude", "sk-test");
+        config.correction_overrides.model = Some("claude-sonnet-4-5".to_string());
+        config.translation_overrides.model = Some("  ".to_string());
+        assert_eq!(model_for(&config, AiTask::Correction, &config.claude_model), "claude-sonnet-4-5");
+        assert_eq!(model_for(&config, AiTask::Translation, &config.claude_model), config.claude_model);
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_exponentially() {
+        let err = AiError::Server("boom".to_string());
+        assert_eq!(retry_delay(0, &err), Some(Duration::from_secs(1)));
+        assert_eq!(retry_delay(1, &err), Some(Duration::from_secs(2)));
+        assert_eq!(retry_delay(2, &err), Some(Duration::from_secs(4)));
+        assert_eq!(retry_delay(MAX_RETRIES, &err), None);
+    }
+
+    #[test]
+    fn test_retry_delay_honors_retry_after() {
+        let err = AiError::RateLimited { message: String::new(), retry_after: Some(Duration::from_secs(7)) };
+        assert_eq!(retry_delay(0, &err), Some(Duration::from_secs(7)));
+        let too_long = AiError::RateLimited { message: String::new(), retry_after: Some(Duration::from_secs(600)) };
+        assert_eq!(retry_delay(0, &too_long), None);
+    }
+
+    #[test]
+    fn test_retry_delay_skips_permanent_errors() {
+        assert_eq!(retry_delay(0, &AiError::Auth("bad key".to_string())), None);
+        assert_eq!(retry_delay(0, &AiError::Timeout("slow".to_string())), None);
+    }
+
+    #[test]
+    fn test_ai_error_serializes_code_and_message() {
+        let err = AiError::Overloaded { message: "busy".to_string(), retry_after: Some(Duration::from_secs(2)) };
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["code"], "overloaded");
+        assert_eq!(json["message"], "busy");
+        assert_eq!(json["retry_after_secs"], 2.0);
+    }
+
+    /// Fails with the queued errors first, then succeeds.
+    struct FlakyProvider {
+        failures: std::sync::Mutex<Vec<AiError>>,
+    }
+
+    impl AiProvider for FlakyProvider {
+        fn id(&self) -> &'static str {
+            "flaky"
+        }
+
+        fn capabilities(&self) -> ProviderCapabilities {
+            ProviderCapabilities { remote: false, requires_api_key: false, streaming: false, structured_output: false }
+        }
+
+        fn chat<'a>(
+            &'a self,
+            _messages: &'a [ChatMessage],
+            _options: ChatOptions<'a>,
+        ) -> BoxFuture<'a, Result<String, AiError>> {
+            Box::pin(async move {
+                match self.failures.lock().unwrap().pop() {
+                    Some(err) => Err(err),
+                    None => Ok("ok".to_string()),
+                }
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_complete_retries_transient_errors() {
+        let overloaded = AiError::Overloaded { message: String::new(), retry_after: Some(Duration::ZERO) };
+        let provider = FlakyProvider { failures: std::sync::Mutex::new(vec![overloaded.clone(), overloaded]) };
+        assert_eq!(provider.complete("sys", "text", ChatOptions::default()).await.unwrap(), "ok");
+    }
+
+    #[tokio::test]
+    async fn test_complete_does_not_retry_permanent_errors() {
+        let provider = FlakyProvider { failures: std::sync::Mutex::new(vec![AiError::Auth("bad key".to_string())]) };
+        assert_eq!(provider.complete("sys", "text", ChatOptions::default()).await.unwrap_err().code(), "auth");
+    }
+
+    #[test]
+    fn test_split_system_joins_system_messages() {
+        let messages = [
+            ChatMessage::new("system", "a"),
+            ChatMessage::new("user", "hi"),
+            ChatMessage::new("system", "b"),
+        ];
+        let (system, rest) = split_system(&messages);
+        assert_eq!(system, "a\n\nb");
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].content, "hi");
+    }
+}
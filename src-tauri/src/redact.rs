@@ -0,0 +1,261 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use crate::config::AppConfig;
+use crate::provider::AiProvider;
+
+/// Kinds of personal details replaced before text goes to a remote provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum PiiKind {
+    Name,
+    Email,
+    Phone,
+    Address,
+}
+
+impl PiiKind {
+    fn label(self) -> &'static str {
+        match self {
+            PiiKind::Name => "NAME",
+            PiiKind::Email => "EMAIL",
+            PiiKind::Phone => "PHONE",
+            PiiKind::Address => "ADDRESS",
+        }
+    }
+}
+
+/// Best-effort detectors. Addresses are only recognized in common English, Italian,
+/// Japanese, Korean and Taiwanese forms; names come from the user's list.
+fn patterns() -> &'static [(PiiKind, Regex)] {
+    static PATTERNS: OnceLock<Vec<(PiiKind, Regex)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            (PiiKind::Email, r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}"),
+            // Grouped numbers such as 03-1234-5678, (555) 123-4567, +39 06 1234 5678
+            (PiiKind::Phone, r"(?:\+\d{1,3}[\s.-]?)?(?:\(\d{1,4}\)\s?|\d{2,4}[\s.-])\d{2,4}[\s.-]\d{3,4}\b"),
+            // Ungrouped: +819012345678, 09012345678
+            (PiiKind::Phone, r"\+\d{8,15}\b|\b0\d{9,10}\b"),
+            (
+                PiiKind::Address,
+                r"\b\d{1,5}\s+(?:[A-Z][A-Za-z]*\.?\s+){1,4}(?:Street|St|Avenue|Ave|Road|Rd|Boulevard|Blvd|Lane|Ln|Drive|Dr|Way|Court|Ct|Place|Pl)\b",
+            ),
+            (PiiKind::Address, r"\b(?:Via|Viale|Piazza|Corso|Vicolo)\s+(?:\p{Lu}[\p{L}']*\s+){0,3}\p{Lu}[\p{L}']*,?\s*\d{1,4}\b"),
+            (PiiKind::Address, r"〒\s?\d{3}-?\d{4}"),
+            (
+                PiiKind::Address,
+                r"(?:東京都|北海道|京都府|大阪府|\p{Han}{2,3}県)\p{Han}{1,8}?[市区町村郡](?:[\p{Han}\p{Katakana}ー]{0,10}\d{1,4}(?:丁目|番地|番|号|[-－]\d{1,4})*)?",
+            ),
+            (PiiKind::Address, r"\p{Hangul}{1,10}(?:시|도)\s?\p{Hangul}{1,10}(?:구|군)\s?\p{Hangul}{1,10}(?:로|길)\s?\d+(?:-\d+)?"),
+            (PiiKind::Address, r"\p{Han}{2,3}[市縣]\p{Han}{1,4}[區鄉鎮市]\p{Han}{1,8}[路街](?:\p{Han}段)?(?:\d+巷)?(?:\d+弄)?\d+號"),
+        ]
+        .into_iter()
+        .map(|(kind, pattern)| (kind, Regex::new(pattern).expect("valid PII pattern")))
+        .collect()
+    })
+}
+
+/// Placeholders as the model may echo them back: `[NAME_1]`, also with other
+/// brackets, spacing or case.
+fn placeholder_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"(?i)[\[［【]\s*(NAME|EMAIL|PHONE|ADDRESS)[_\s]?(\d+)\s*[\]］】]").expect("valid placeholder pattern")
+    })
+}
+
+/// Whether a phone pattern match is likely a phone number rather than a date or
+/// an amount: it starts with `+` or `(`, or has at least 9 digits and is not
+/// shaped like a date (24.02.2026, 2026-02-24).
+fn is_phone_like(candidate: &str) -> bool {
+    if candidate.starts_with(['+', '(']) {
+        return true;
+    }
+    let groups: Vec<usize> = candidate
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|g| !g.is_empty())
+        .map(str::len)
+        .collect();
+    let is_date = matches!(groups[..], [1..=2, 1..=2, 4] | [4, 1..=2, 1..=2]);
+    !is_date && groups.iter().sum::<usize>() >= 9
+}
+
+fn is_word_char(c: Option<char>) -> bool {
+    c.is_some_and(|c| c.is_ascii_alphanumeric())
+}
+
+/// Occurrences of the listed names, case-insensitively. A name starting or ending
+/// in a Latin letter must not continue a longer word ("Ann" in "Announce"), but may
+/// touch CJK text ("Annは").
+fn name_spans(text: &str, names: &[String]) -> Vec<(usize, usize, PiiKind)> {
+    let mut spans = vec![];
+    for name in names.iter().map(|n| n.trim()).filter(|n| !n.is_empty()) {
+        let Ok(pattern) = Regex::new(&format!("(?i){}", regex::escape(name))) else { continue };
+        for m in pattern.find_iter(text) {
+            let before = text[..m.start()].chars().next_back();
+            let after = text[m.end()..].chars().next();
+            let first = m.as_str().chars().next();
+            let last = m.as_str().chars().next_back();
+            if (is_word_char(first) && is_word_char(before)) || (is_word_char(last) && is_word_char(after)) {
+                continue;
+            }
+            spans.push((m.start(), m.end(), PiiKind::Name));
+        }
+    }
+    spans
+}
+
+/// Replaces personal details with numbered placeholders and puts them back in the
+/// model's reply. One instance serves a whole request, so the same value always
+/// gets the same placeholder, in every text of that request.
+pub struct Redactor {
+    enabled: bool,
+    names: Vec<String>,
+    by_value: HashMap<(PiiKind, String), String>,
+    originals: HashMap<String, String>,
+    counts: HashMap<PiiKind, usize>,
+}
+
+impl Redactor {
+    pub fn new(enabled: bool, names: Vec<String>) -> Self {
+        Redactor { enabled, names, by_value: HashMap::new(), originals: HashMap::new(), counts: HashMap::new() }
+    }
+
+    /// Active only when turned on in Settings and `ai` sends text off this machine.
+    pub fn for_provider(config: &AppConfig, ai: &dyn AiProvider) -> Self {
+        Self::new(config.redact_pii && ai.capabilities().remote, config.redaction_names.clone())
+    }
+
+    fn placeholder(&mut self, kind: PiiKind, value: &str) -> String {
+        if let Some(existing) = self.by_value.get(&(kind, value.to_string())) {
+            return existing.clone();
+        }
+        let count = self.counts.entry(kind).or_insert(0);
+        *count += 1;
+        let placeholder = format!("[{}_{}]", kind.label(), count);
+        self.by_value.insert((kind, value.to_string()), placeholder.clone());
+        self.originals.insert(placeholder.clone(), value.to_string());
+        placeholder
+    }
+
+    /// `text` with every detected detail replaced by its placeholder.
+    pub fn redact(&mut self, text: &str) -> String {
+        if !self.enabled {
+            return text.to_string();
+        }
+        let mut spans = name_spans(text, &self.names);
+        for (kind, pattern) in patterns() {
+            spans.extend(
+                pattern
+                    .find_iter(text)
+                    .filter(|m| *kind != PiiKind::Phone || is_phone_like(m.as_str()))
+                    .map(|m| (m.start(), m.end(), *kind)),
+            );
+        }
+        // Leftmost first, and the longest of those starting at the same place
+        spans.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+
+        let mut out = String::with_capacity(text.len());
+        let mut pos = 0;
+        for (start, end, kind) in spans {
+            if start < pos {
+                continue;
+            }
+            out.push_str(&text[pos..start]);
+            let placeholder = self.placeholder(kind, &text[start..end]);
+            out.push_str(&placeholder);
+            pos = end;
+        }
+        out.push_str(&text[pos..]);
+        out
+    }
+
+    /// Tell the model to leave placeholders alone, if anything was replaced.
+    pub fn annotate_prompt(&self, system: String) -> String {
+        if self.originals.is_empty() {
+            return system;
+        }
+        format!(
+            "{}\n\nSome personal details were replaced with placeholders such as [NAME_1] or [EMAIL_1]. \
+            Keep every placeholder exactly as written; do not translate, correct or remove it.",
+            system
+        )
+    }
+
+    /// `text` with the placeholders of this request replaced by the original values.
+    /// Unknown placeholders are left as they are.
+    pub fn restore(&self, text: &str) -> String {
+        if self.originals.is_empty() {
+            return text.to_string();
+        }
+        placeholder_pattern()
+            .replace_all(text, |caps: &regex::Captures| {
+                let key = format!("[{}_{}]", caps[1].to_uppercase(), &caps[2]);
+                self.originals.get(&key).cloned().unwrap_or_else(|| caps[0].to_string())
+            })
+            .into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redactor(names: &[&str]) -> Redactor {
+        Redactor::new(true, names.iter().map(|n| n.to_string()).collect())
+    }
+
+    #[test]
+    fn test_redact_and_restore_round_trip() {
+        let mut r = redactor(&["Maria Rossi", "田中"]);
+        let text = "Dinner with Maria Rossi and 田中さん. Mail maria@example.com or call 090-1234-5678. \
+                    maria rossi lives at 221 Baker Street.";
+        let sent = r.redact(text);
+        assert_eq!(
+            sent,
+            "Dinner with [NAME_1] and [NAME_2]さん. Mail [EMAIL_1] or call [PHONE_1]. \
+             [NAME_3] lives at [ADDRESS_1]."
+        );
+        assert_eq!(r.restore(&sent), text);
+        // Same value, same placeholder, across texts of one request
+        assert_eq!(r.redact("Maria Rossi again"), "[NAME_1] again");
+    }
+
+    #[test]
+    fn test_restore_tolerates_model_rewrites() {
+        let mut r = redactor(&["Anna"]);
+        assert_eq!(r.redact("Anna called."), "[NAME_1] called.");
+        assert_eq!(r.restore("【NAME_1】さんから電話。[name 1] [ NAME_1 ] [NAME_9]"), "Annaさんから電話。Anna Anna [NAME_9]");
+    }
+
+    #[test]
+    fn test_names_respect_word_boundaries() {
+        let mut r = redactor(&["Ann"]);
+        assert_eq!(r.redact("Announcement: Ann is here. Annは来た。"), "Announcement: [NAME_1] is here. [NAME_1]は来た。");
+    }
+
+    #[test]
+    fn test_detects_regional_formats() {
+        let mut r = redactor(&[]);
+        assert_eq!(r.redact("〒150-0001 東京都渋谷区神宮前1-2-3"), "[ADDRESS_1] [ADDRESS_2]");
+        assert_eq!(r.redact("Abito in Via Giuseppe Verdi 12, chiamami al +39 06 1234 5678"), "Abito in [ADDRESS_3], chiamami al [PHONE_1]");
+        assert_eq!(r.redact("서울시 강남구 테헤란로 152"), "[ADDRESS_4]");
+        assert_eq!(r.redact("台北市大安區忠孝東路四段100號"), "[ADDRESS_5]");
+    }
+
+    #[test]
+    fn test_leaves_ordinary_text_alone() {
+        let mut r = redactor(&[]);
+        let text = "On 2026-02-24 at 10:30 I ran 5 km and read 120 pages.";
+        assert_eq!(r.redact(text), text);
+        for text in ["Nato il 24.02.2026", "Due 24-02-2026", "Paid 12.345.678 won"] {
+            assert_eq!(r.redact(text), text);
+        }
+        assert!(is_phone_like("555-123-4567"));
+        assert!(is_phone_like("(555) 123-4567"));
+        assert!(!is_phone_like("24.02.2026"));
+        assert_eq!(r.annotate_prompt("sys".to_string()), "sys");
+
+        let mut off = Redactor::new(false, vec!["Anna".to_string()]);
+        assert_eq!(off.redact("Anna: anna@example.com"), "Anna: anna@example.com");
+    }
+}
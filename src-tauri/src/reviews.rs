@@ -0,0 +1,347 @@
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use crate::storage::{self, DiaryEntry};
+use crate::vocabulary::VocabItem;
+
+const DECK_FILE: &str = "reviews.json";
+
+/// Serializes read-modify-write cycles on the review file across commands.
+static DECK_LOCK: Mutex<()> = Mutex::new(());
+
+// --- Scheduling (SM-2) ---
+
+const INITIAL_EASE: f32 = 2.5;
+const MIN_EASE: f32 = 1.3;
+
+/// How well the user remembered a card.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewGrade {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl ReviewGrade {
+    /// SM-2 response quality (0–5).
+    fn quality(self) -> f32 {
+        match self {
+            ReviewGrade::Again => 1.0,
+            ReviewGrade::Hard => 3.0,
+            ReviewGrade::Good => 4.0,
+            ReviewGrade::Easy => 5.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Schedule {
+    pub ease: f32,
+    pub interval_days: u32,
+    /// Successful reviews in a row.
+    pub repetitions: u32,
+    /// Times the card was forgotten after having been learned.
+    pub lapses: u32,
+    pub due: NaiveDate,
+    pub last_reviewed: Option<NaiveDate>,
+}
+
+impl Schedule {
+    fn new(today: NaiveDate) -> Self {
+        Schedule {
+            ease: INITIAL_EASE,
+            interval_days: 0,
+            repetitions: 0,
+            lapses: 0,
+            due: today,
+            last_reviewed: None,
+        }
+    }
+
+    /// Apply one review graded on `today`.
+    fn grade(&mut self, grade: ReviewGrade, today: NaiveDate) {
+        let q = grade.quality();
+        if grade == ReviewGrade::Again {
+            if self.repetitions > 0 {
+                self.lapses += 1;
+            }
+            self.repetitions = 0;
+            self.interval_days = 1;
+        } else {
+            self.repetitions += 1;
+            self.interval_days = match self.repetitions {
+                1 => 1,
+                2 => 6,
+                _ => ((self.interval_days as f32) * self.ease).round().max(1.0) as u32,
+            };
+        }
+        self.ease = (self.ease + 0.1 - (5.0 - q) * (0.08 + (5.0 - q) * 0.02)).max(MIN_EASE);
+        self.due = today + Duration::days(i64::from(self.interval_days));
+        self.last_reviewed = Some(today);
+    }
+}
+
+// --- Cards ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CardKind {
+    /// A corrected phrase: front is what the user wrote, back the correction.
+    Mistake,
+    /// A vocabulary notebook word: front is the word, back its meaning.
+    Vocabulary,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewCard {
+    /// Derived from the card's source, so re-syncing finds the same card.
+    pub id: String,
+    pub kind: CardKind,
+    pub language: String,
+    pub front: String,
+    pub back: String,
+    /// Why it was corrected, or the word's reading.
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Sentence the phrase or word appeared in.
+    #[serde(default)]
+    pub context: Option<String>,
+    #[serde(default)]
+    pub source_entry_id: Option<String>,
+    pub schedule: Schedule,
+}
+
+/// Contents of `reviews.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Deck {
+    #[serde(default)]
+    pub cards: Vec<ReviewCard>,
+}
+
+/// The sentence of `text` that contains `span`, if any.
+pub(crate) fn sentence_containing(text: &str, span: &str) -> Option<String> {
+    let pos = text.find(span)?;
+    let is_boundary = |c: char| matches!(c, '.' | '!' | '?' | '。' | '！' | '？' | '\n');
+    let start = text[..pos].rfind(is_boundary).map(|i| {
+        i + text[i..].chars().next().map_or(1, char::len_utf8)
+    }).unwrap_or(0);
+    let end = text[pos + span.len()..]
+        .find(is_boundary)
+        .map(|i| {
+            let i = pos + span.len() + i;
+            // Keep the sentence's own punctuation, but not a line break
+            if text[i..].starts_with('\n') { i } else { i + text[i..].chars().next().map_or(1, char::len_utf8) }
+        })
+        .unwrap_or(text.len());
+    let sentence = text[start..end].trim();
+    (!sentence.is_empty()).then(|| sentence.to_string())
+}
+
+/// Cards that `entries` and `vocabulary` currently call for, with fresh schedules.
+fn source_cards(entries: &[DiaryEntry], vocabulary: &[VocabItem], today: NaiveDate) -> Vec<ReviewCard> {
+    let mut cards = vec![];
+    for entry in entries {
+        for (language, edits) in &entry.edits {
+            // Hint-mode edits carry no correction to learn from
+            for edit in edits.iter().filter(|e| !e.replacement.is_empty()) {
+                cards.push(ReviewCard {
+                    id: format!("mistake:{}:{}:{}→{}", entry.meta.id, language, edit.original, edit.replacement),
+                    kind: CardKind::Mistake,
+                    language: language.clone(),
+                    front: edit.original.clone(),
+                    back: edit.replacement.clone(),
+                    note: Some(edit.reason.clone()).filter(|r| !r.trim().is_empty()),
+                    context: sentence_containing(&entry.original, &edit.original),
+                    source_entry_id: Some(entry.meta.id.clone()),
+                    schedule: Schedule::new(today),
+                });
+            }
+        }
+    }
+    for item in vocabulary {
+        cards.push(ReviewCard {
+            id: format!("vocabulary:{}", item.id),
+            kind: CardKind::Vocabulary,
+            language: item.language.clone(),
+            front: item.lemma.clone(),
+            back: item.meaning.clone(),
+            note: item.reading.clone(),
+            context: item.example.clone(),
+            source_entry_id: item.source_entry_id.clone(),
+            schedule: Schedule::new(today),
+        });
+    }
+    // The same fix can be listed twice in one entry; keep one card for it
+    let mut seen = HashSet::new();
+    cards.retain(|card| seen.insert(card.id.clone()));
+    cards
+}
+
+impl Deck {
+    /// Add cards for new mistakes and words, refresh the text of existing ones and
+    /// drop cards whose source is gone. Schedules of existing cards are kept.
+    pub fn sync(&mut self, entries: &[DiaryEntry], vocabulary: &[VocabItem], today: NaiveDate) {
+        let sources = source_cards(entries, vocabulary, today);
+        let ids: HashSet<&str> = sources.iter().map(|c| c.id.as_str()).collect();
+        self.cards.retain(|card| ids.contains(card.id.as_str()));
+        for source in sources {
+            match self.cards.iter_mut().find(|card| card.id == source.id) {
+                Some(card) => {
+                    let schedule = std::mem::replace(&mut card.schedule, Schedule::new(today));
+                    *card = ReviewCard { schedule, ..source };
+                }
+                None => self.cards.push(source),
+            }
+        }
+    }
+
+    /// Cards due on or before `today`, most overdue first.
+    pub fn due(&self, today: NaiveDate, language: Option<&str>, limit: Option<usize>) -> Vec<ReviewCard> {
+        let mut due: Vec<ReviewCard> = self
+            .cards
+            .iter()
+            .filter(|card| card.schedule.due <= today)
+            .filter(|card| language.map_or(true, |l| card.language == l))
+            .cloned()
+            .collect();
+        due.sort_by(|a, b| a.schedule.due.cmp(&b.schedule.due).then_with(|| a.id.cmp(&b.id)));
+        if let Some(limit) = limit {
+            due.truncate(limit);
+        }
+        due
+    }
+
+    pub fn grade(&mut self, card_id: &str, grade: ReviewGrade, today: NaiveDate) -> Result<ReviewCard, String> {
+        let card = self
+            .cards
+            .iter_mut()
+            .find(|card| card.id == card_id)
+            .ok_or_else(|| format!("Review card not found: {}", card_id))?;
+        card.schedule.grade(grade, today);
+        Ok(card.clone())
+    }
+}
+
+// --- File access ---
+
+fn deck_path() -> Result<PathBuf, String> {
+    Ok(storage::entries_dir()?.join(DECK_FILE))
+}
+
+/// Load, change and save the review deck as one step.
+pub fn modify<T>(f: impl FnOnce(&mut Deck) -> Result<T, String>) -> Result<T, String> {
+    let _guard = DECK_LOCK.lock().map_err(|e| e.to_string())?;
+    let path = deck_path()?;
+    let mut deck: Deck = storage::read_json_file(&path, "Review schedule")?;
+    let result = f(&mut deck)?;
+    storage::write_json_file(&path, &deck)?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::CorrectionEdit;
+    use crate::mistakes::MistakeCategory;
+    use crate::storage::test_support;
+    use std::collections::HashMap;
+
+    fn day(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn entry(id: &str, original: &str, edits: Vec<(&str, &str)>) -> DiaryEntry {
+        let mut entry = test_support::entry(id);
+        entry.meta.languages = vec!["en".to_string()];
+        entry.original = original.to_string();
+        entry.edits = HashMap::from([(
+            "en".to_string(),
+            edits
+                .into_iter()
+                .map(|(o, r)| CorrectionEdit {
+                    original: o.to_string(),
+                    replacement: r.to_string(),
+                    category: MistakeCategory::Tense,
+                    reason: "past tense".to_string(),
+                })
+                .collect(),
+        )]);
+        entry
+    }
+
+    #[test]
+    fn test_sm2_intervals_grow() {
+        let today = day("2026-03-01");
+        let mut schedule = Schedule::new(today);
+        schedule.grade(ReviewGrade::Good, today);
+        assert_eq!((schedule.interval_days, schedule.due), (1, day("2026-03-02")));
+        schedule.grade(ReviewGrade::Good, day("2026-03-02"));
+        assert_eq!(schedule.interval_days, 6);
+        schedule.grade(ReviewGrade::Good, day("2026-03-08"));
+        assert_eq!(schedule.interval_days, 15);
+        assert_eq!(schedule.due, day("2026-03-23"));
+        assert!((schedule.ease - 2.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_sm2_again_resets_and_counts_lapse() {
+        let today = day("2026-03-01");
+        let mut schedule = Schedule::new(today);
+        schedule.grade(ReviewGrade::Easy, today);
+        schedule.grade(ReviewGrade::Again, day("2026-03-02"));
+        assert_eq!(schedule.repetitions, 0);
+        assert_eq!(schedule.lapses, 1);
+        assert_eq!(schedule.due, day("2026-03-03"));
+        assert!(schedule.ease < 2.6);
+        for _ in 0..10 {
+            schedule.grade(ReviewGrade::Again, today);
+        }
+        assert!((schedule.ease - MIN_EASE).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_sentence_containing() {
+        let text = "I was tired. Yesterday I goed to the park! Then home.";
+        assert_eq!(sentence_containing(text, "goed").as_deref(), Some("Yesterday I goed to the park!"));
+        assert_eq!(sentence_containing("今日は晴れ。学校を行きました。", "学校を").as_deref(), Some("学校を行きました。"));
+        assert_eq!(sentence_containing("first line\nsecond line", "second").as_deref(), Some("second line"));
+        assert_eq!(sentence_containing(text, "missing"), None);
+    }
+
+    #[test]
+    fn test_sync_creates_keeps_and_removes_cards() {
+        let today = day("2026-03-01");
+        let mut deck = Deck::default();
+        let entries = vec![entry("2026-03-01_100000", "I goed home. I eated.", vec![("goed", "went"), ("eated", "ate"), ("hint", "")])];
+        deck.sync(&entries, &[], today);
+        assert_eq!(deck.cards.len(), 2);
+        assert_eq!(deck.cards[0].context.as_deref(), Some("I goed home."));
+
+        let id = deck.cards[0].id.clone();
+        deck.grade(&id, ReviewGrade::Good, today).unwrap();
+
+        let entries = vec![entry("2026-03-01_100000", "I goed home.", vec![("goed", "went")])];
+        deck.sync(&entries, &[], today);
+        assert_eq!(deck.cards.len(), 1);
+        assert_eq!(deck.cards[0].schedule.repetitions, 1);
+    }
+
+    #[test]
+    fn test_due_filters_and_orders() {
+        let today = day("2026-03-10");
+        let mut deck = Deck::default();
+        deck.sync(&[entry("2026-03-01_100000", "a b c", vec![("a", "A"), ("b", "B"), ("c", "C")])], &[], day("2026-03-01"));
+        let ids: Vec<String> = deck.cards.iter().map(|c| c.id.clone()).collect();
+        deck.grade(&ids[0], ReviewGrade::Good, day("2026-03-09")).unwrap(); // due 03-10
+        deck.grade(&ids[1], ReviewGrade::Easy, day("2026-03-10")).unwrap(); // due 03-11
+        let due = deck.due(today, None, None);
+        assert_eq!(due.iter().map(|c| &c.id).collect::<Vec<_>>(), vec![&ids[2], &ids[0]]);
+        assert_eq!(deck.due(today, None, Some(1)).len(), 1);
+        assert!(deck.due(today, Some("ja"), None).is_empty());
+        assert!(deck.grade("missing", ReviewGrade::Good, today).is_err());
+    }
+}
@@ -0,0 +1,767 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+use chrono::NaiveDate;
+use crate::diff::is_cjk;
+use crate::storage::{self, DiaryEntry, EntryListItem};
+use crate::vault;
+
+const INDEX_FILE: &str = ".search-index.json";
+/// Bump when tokenization or the stored layout changes; older files are rebuilt.
+const INDEX_VERSION: u32 = 1;
+/// Title matches count this many times a body match.
+const TITLE_WEIGHT: f32 = 3.0;
+const SNIPPET_BEFORE: usize = 30;
+const SNIPPET_AFTER: usize = 90;
+
+// --- Tokenizing ---
+
+/// A term and the byte range it came from.
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    term: String,
+    start: usize,
+    end: usize,
+}
+
+/// Lowercased words for space-delimited scripts; for CJK every character and every
+/// pair of neighbouring characters, so any substring of two or more characters can
+/// be found without a dictionary.
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let end_of = |i: usize| chars.get(i + 1).map_or(text.len(), |&(pos, _)| pos);
+    let mut i = 0;
+    while i < chars.len() {
+        let (start, c) = chars[i];
+        if is_cjk(c) {
+            tokens.push(Token { term: c.to_string(), start, end: end_of(i) });
+            if let Some(&(_, next)) = chars.get(i + 1).filter(|(_, n)| is_cjk(*n)) {
+                tokens.push(Token { term: format!("{}{}", c, next), start, end: end_of(i + 1) });
+            }
+            i += 1;
+        } else if c.is_alphanumeric() {
+            let mut j = i;
+            while j + 1 < chars.len() && chars[j + 1].1.is_alphanumeric() && !is_cjk(chars[j + 1].1) {
+                j += 1;
+            }
+            let end = end_of(j);
+            tokens.push(Token { term: text[start..end].to_lowercase(), start, end });
+            i = j + 1;
+        } else {
+            i += 1;
+        }
+    }
+    tokens
+}
+
+/// One thing every result must contain.
+#[derive(Debug, Clone, PartialEq)]
+struct QueryTerm {
+    term: String,
+    /// Words also match longer words ("walk" finds "walking"); CJK terms match exactly.
+    prefix: bool,
+}
+
+fn query_terms(query: &str) -> Vec<QueryTerm> {
+    let tokens = tokenize(query);
+    let mut terms: Vec<QueryTerm> = vec![];
+    for (i, token) in tokens.iter().enumerate() {
+        let first = token.term.chars().next().unwrap_or(' ');
+        let term = if !is_cjk(first) {
+            QueryTerm { term: token.term.clone(), prefix: true }
+        } else if token.term.chars().count() == 2 {
+            QueryTerm { term: token.term.clone(), prefix: false }
+        } else {
+            // A CJK unigram is only needed when it has no bigram partner on either side
+            let paired = tokens.get(i + 1).is_some_and(|t| t.start == token.start && t.term.chars().count() == 2)
+                || (i > 0 && tokens[i - 1].end == token.end && tokens[i - 1].term.chars().count() == 2);
+            if paired {
+                continue;
+            }
+            QueryTerm { term: token.term.clone(), prefix: false }
+        };
+        if !terms.contains(&term) {
+            terms.push(term);
+        }
+    }
+    terms
+}
+
+// --- Filters ---
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSort {
+    /// Best match first; newest first when there are no search words.
+    #[default]
+    Relevance,
+    /// Newest first.
+    Date,
+}
+
+/// Restrictions on a search. Each can also be typed into the query as an operator:
+/// `from:2026-01-01 to:2026-03-31 mode:correction lang:it in:original sort:date`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchFilters {
+    #[serde(default)]
+    pub from: Option<NaiveDate>,
+    #[serde(default)]
+    pub to: Option<NaiveDate>,
+    #[serde(default)]
+    pub mode: Option<String>,     // "correction" or "translation"
+    #[serde(default)]
+    pub language: Option<String>, // entries with this target language
+    /// Only match in one section: "title", "original", or a language code.
+    #[serde(default)]
+    pub section: Option<String>,
+    #[serde(default)]
+    pub sort: Option<SearchSort>,
+}
+
+impl SearchFilters {
+    /// Fill the fields left unset here from `other`.
+    fn or(self, other: SearchFilters) -> SearchFilters {
+        SearchFilters {
+            from: self.from.or(other.from),
+            to: self.to.or(other.to),
+            mode: self.mode.or(other.mode),
+            language: self.language.or(other.language),
+            section: self.section.or(other.section),
+            sort: self.sort.or(other.sort),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == SearchFilters::default()
+    }
+
+    fn accepts(&self, meta: &EntryListItem) -> bool {
+        let date = NaiveDate::parse_from_str(&meta.date, "%Y-%m-%d").ok();
+        let in_range = match (self.from, self.to) {
+            (None, None) => true,
+            (from, to) => date.is_some_and(|d| from.map_or(true, |f| d >= f) && to.map_or(true, |t| d <= t)),
+        };
+        in_range
+            && self.mode.as_ref().map_or(true, |m| meta.mode == *m)
+            && self.language.as_ref().map_or(true, |l| meta.languages.contains(l))
+    }
+}
+
+/// Split the operators out of a typed query, returning the remaining words and the
+/// filters. Words with an unknown `key:` prefix are kept as search words.
+fn parse_query(query: &str) -> Result<(String, SearchFilters), String> {
+    let parse_date = |value: &str| {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| format!("Invalid date in search: {}", value))
+    };
+    let mut filters = SearchFilters::default();
+    let mut words = vec![];
+    for word in query.split_whitespace() {
+        let Some((key, value)) = word.split_once(':').filter(|(_, v)| !v.is_empty()) else {
+            words.push(word);
+            continue;
+        };
+        match key.to_lowercase().as_str() {
+            "from" => filters.from = Some(parse_date(value)?),
+            "to" => filters.to = Some(parse_date(value)?),
+            "mode" => filters.mode = Some(value.to_lowercase()),
+            "lang" => filters.language = Some(value.to_string()),
+            "in" => filters.section = Some(value.to_string()),
+            "sort" => {
+                filters.sort = Some(match value.to_lowercase().as_str() {
+                    "relevance" => SearchSort::Relevance,
+                    "date" => SearchSort::Date,
+                    _ => return Err(format!("Unknown search sort: {}", value)),
+                })
+            }
+            _ => words.push(word),
+        }
+    }
+    Ok((words.join(" "), filters))
+}
+
+// --- Index ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedSection {
+    /// "title", "original", or a language code for a translation/correction.
+    name: String,
+    len: u32,
+    terms: HashMap<String, u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedDoc {
+    path: PathBuf,
+    /// File modification time (ms since epoch) when indexed.
+    modified: u64,
+    meta: EntryListItem,
+    sections: Vec<IndexedSection>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IndexFile {
+    version: u32,
+    docs: HashMap<String, IndexedDoc>,
+}
+
+/// Loaded index plus the term → entry ids postings derived from it.
+#[derive(Default)]
+struct SearchIndex {
+    docs: HashMap<String, IndexedDoc>,
+    postings: HashMap<String, HashSet<String>>,
+}
+
+static INDEX: Mutex<Option<SearchIndex>> = Mutex::new(None);
+
+fn section(name: &str, text: &str) -> IndexedSection {
+    let tokens = tokenize(text);
+    let mut terms = HashMap::new();
+    for token in &tokens {
+        *terms.entry(token.term.clone()).or_insert(0) += 1;
+    }
+    IndexedSection { name: name.to_string(), len: tokens.len() as u32, terms }
+}
+
+/// The searchable sections of an entry, in the order snippets prefer them.
+fn entry_sections(entry: &DiaryEntry) -> Vec<(String, &str)> {
+    let mut sections = vec![("original".to_string(), entry.original.as_str())];
+    for lang in &entry.meta.languages {
+        if let Some(text) = entry.translations.get(lang) {
+            sections.push((lang.clone(), text.as_str()));
+        }
+    }
+    sections.push(("title".to_string(), entry.meta.title.as_str()));
+    sections
+}
+
+fn list_item(entry: &DiaryEntry) -> EntryListItem {
+    let meta = &entry.meta;
+    EntryListItem {
+        id: meta.id.clone(),
+        date: meta.date.clone(),
+        title: meta.title.clone(),
+        mode: meta.mode.clone(),
+        languages: meta.languages.clone(),
+        created_at: meta.created_at.clone(),
+        updated_at: meta.updated_at.clone(),
+    }
+}
+
+fn modified_ms(path: &Path) -> u64 {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+impl SearchIndex {
+    fn from_file(file: IndexFile) -> Self {
+        let mut index = SearchIndex::default();
+        for (_, doc) in file.docs {
+            index.insert(doc);
+        }
+        index
+    }
+
+    fn to_file(&self) -> IndexFile {
+        IndexFile { version: INDEX_VERSION, docs: self.docs.clone() }
+    }
+
+    fn insert(&mut self, doc: IndexedDoc) {
+        self.remove(&doc.meta.id);
+        for section in &doc.sections {
+            for term in section.terms.keys() {
+                self.postings.entry(term.clone()).or_default().insert(doc.meta.id.clone());
+            }
+        }
+        self.docs.insert(doc.meta.id.clone(), doc);
+    }
+
+    fn remove(&mut self, id: &str) -> bool {
+        let Some(doc) = self.docs.remove(id) else { return false };
+        for section in &doc.sections {
+            for term in section.terms.keys() {
+                if let Some(ids) = self.postings.get_mut(term) {
+                    ids.remove(id);
+                    if ids.is_empty() {
+                        self.postings.remove(term);
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    fn index_entry(&mut self, path: &Path, entry: &DiaryEntry) {
+        let sections = entry_sections(entry)
+            .into_iter()
+            .map(|(name, text)| section(&name, text))
+            .collect();
+        self.insert(IndexedDoc {
+            path: path.to_path_buf(),
+            modified: modified_ms(path),
+            meta: list_item(entry),
+            sections,
+        });
+    }
+
+    /// Bring the index in line with the files on disk, reading only files that are
+    /// new or changed since they were indexed. Returns whether anything changed.
+    fn refresh(&mut self, paths: &[PathBuf]) -> bool {
+        let on_disk: HashSet<&PathBuf> = paths.iter().collect();
+        let stale: Vec<String> = self
+            .docs
+            .values()
+            .filter(|doc| !on_disk.contains(&doc.path))
+            .map(|doc| doc.meta.id.clone())
+            .collect();
+        let mut changed = !stale.is_empty();
+        for id in stale {
+            self.remove(&id);
+        }
+
+        let indexed: HashMap<&Path, u64> = self.docs.values().map(|d| (d.path.as_path(), d.modified)).collect();
+        let outdated: Vec<&PathBuf> = paths
+            .iter()
+            .filter(|p| indexed.get(p.as_path()) != Some(&modified_ms(p)))
+            .collect();
+        for path in outdated {
+            changed = true;
+            match storage::read_entry_file(path) {
+                Ok(entry) => self.index_entry(path, &entry),
+                Err(e) => log::warn!("Not indexing {}: {}", path.display(), e),
+            }
+        }
+        changed
+    }
+
+    /// Ids containing every query term, ranked by BM25. With `section` set, only that
+    /// section ("title", "original" or a language code) is searched.
+    fn rank(&self, terms: &[QueryTerm], section: Option<&str>) -> Vec<(String, f32)> {
+        // Each query term expands to the indexed terms it matches
+        let expanded: Vec<Vec<&String>> = terms
+            .iter()
+            .map(|q| {
+                if q.prefix {
+                    self.postings.keys().filter(|t| t.starts_with(&q.term)).collect()
+                } else {
+                    self.postings.get_key_value(&q.term).map(|(t, _)| vec![t]).unwrap_or_default()
+                }
+            })
+            .collect();
+
+        let mut candidates: Option<HashSet<&String>> = None;
+        for matches in &expanded {
+            let ids: HashSet<&String> = matches.iter().flat_map(|t| self.postings[*t].iter()).collect();
+            candidates = Some(match candidates {
+                Some(c) => c.intersection(&ids).copied().collect(),
+                None => ids,
+            });
+        }
+        let Some(candidates) = candidates else { return vec![] };
+
+        let n = self.docs.len() as f32;
+        let doc_len = |doc: &IndexedDoc| doc.sections.iter().map(|s| s.len).sum::<u32>() as f32;
+        let avg_len = (self.docs.values().map(doc_len).sum::<f32>() / n.max(1.0)).max(1.0);
+        let (k1, b) = (1.2, 0.75);
+
+        let mut ranked: Vec<(String, f32)> = candidates
+            .into_iter()
+            .filter_map(|id| {
+                let doc = &self.docs[id];
+                let norm = k1 * (1.0 - b + b * doc_len(doc) / avg_len);
+                let mut score = 0.0;
+                for matches in &expanded {
+                    let mut found = false;
+                    for term in matches {
+                        let tf: f32 = doc
+                            .sections
+                            .iter()
+                            .filter(|s| section.map_or(true, |name| s.name == name))
+                            .map(|s| {
+                                let weight = if s.name == "title" { TITLE_WEIGHT } else { 1.0 };
+                                s.terms.get(*term).copied().unwrap_or(0) as f32 * weight
+                            })
+                            .sum();
+                        if tf > 0.0 {
+                            let df = self.postings[*term].len() as f32;
+                            let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+                            score += idf * tf * (k1 + 1.0) / (tf + norm);
+                            found = true;
+                        }
+                    }
+                    // The postings are per entry, so a restricted search must
+                    // check that the term is in the requested section
+                    if !found {
+                        return None;
+                    }
+                }
+                Some((id.clone(), score))
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| b.0.cmp(&a.0)));
+        ranked
+    }
+}
+
+// --- Snippets ---
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SnippetPart {
+    pub text: String,
+    pub highlight: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Snippet {
+    /// "title", "original", or a language code.
+    pub section: String,
+    pub parts: Vec<SnippetPart>,
+}
+
+fn matches_query(term: &str, terms: &[QueryTerm]) -> bool {
+    terms.iter().any(|q| if q.prefix { term.starts_with(&q.term) } else { term == q.term })
+}
+
+/// Largest char boundary in `text` at or before `i`.
+fn floor_boundary(text: &str, mut i: usize) -> usize {
+    i = i.min(text.len());
+    while !text.is_char_boundary(i) {
+        i -= 1;
+    }
+    i
+}
+
+/// Excerpt of the first section (or of `section` only) that contains a query term,
+/// with matches marked.
+fn snippet(entry: &DiaryEntry, terms: &[QueryTerm], section: Option<&str>) -> Option<Snippet> {
+    entry_sections(entry).into_iter().find_map(|(name, text)| {
+        if section.is_some_and(|s| s != name) {
+            return None;
+        }
+        let mut ranges: Vec<(usize, usize)> = tokenize(text)
+            .into_iter()
+            .filter(|t| matches_query(&t.term, terms))
+            .map(|t| (t.start, t.end))
+            .collect();
+        if ranges.is_empty() {
+            return None;
+        }
+        ranges.sort();
+        let mut merged: Vec<(usize, usize)> = vec![];
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+
+        let window_start = floor_boundary(text, merged[0].0.saturating_sub(SNIPPET_BEFORE));
+        let window_end = floor_boundary(text, merged[0].1 + SNIPPET_AFTER);
+        let mut parts = vec![];
+        let mut cursor = window_start;
+        let mut push = |text: &str, highlight: bool| {
+            if !text.is_empty() {
+                parts.push(SnippetPart { text: text.replace('\n', " "), highlight });
+            }
+        };
+        if window_start > 0 {
+            push("…", false);
+        }
+        for (start, end) in merged.into_iter().filter(|&(s, _)| s < window_end) {
+            let end = end.min(window_end);
+            push(&text[cursor..start], false);
+            push(&text[start..end], true);
+            cursor = end;
+        }
+        push(&text[cursor..window_end], false);
+        if window_end < text.len() {
+            push("…", false);
+        }
+        Some(Snippet { section: name, parts })
+    })
+}
+
+// --- Public API ---
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResult {
+    #[serde(flatten)]
+    pub entry: EntryListItem,
+    pub score: f32,
+    /// Where the words were found; None for a filter-only search.
+    pub snippet: Option<Snippet>,
+}
+
+fn index_path() -> Result<PathBuf, String> {
+    Ok(storage::entries_dir()?.join(INDEX_FILE))
+}
+
+/// Run `f` on the loaded index, loading it from disk (or building it) first.
+/// The index is written back when `f` reports a change.
+fn with_index<T>(f: impl FnOnce(&mut SearchIndex) -> (T, bool)) -> Result<T, String> {
+    let mut guard = INDEX.lock().map_err(|e| e.to_string())?;
+    let path = index_path()?;
+    let index = guard.get_or_insert_with(|| {
+        match storage::read_json_file::<IndexFile>(&path, "Search index") {
+            Ok(file) if file.version == INDEX_VERSION => SearchIndex::from_file(file),
+            Ok(_) => SearchIndex::default(),
+            Err(e) => {
+                log::warn!("{}; rebuilding", e);
+                SearchIndex::default()
+            }
+        }
+    });
+    let (result, changed) = f(index);
+    if changed {
+        // No backup: a lost index is simply rebuilt
+        let json = serde_json::to_string(&index.to_file()).map_err(|e| e.to_string())?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        vault::write(&path, json.as_bytes(), false)?;
+    }
+    Ok(result)
+}
+
+/// Entries containing every word of `query` and passing the filters, each with a
+/// highlighted excerpt of the section that matched. Operators typed into `query`
+/// apply only where `filters` leaves a field unset.
+pub fn search(query: &str, filters: SearchFilters) -> Result<Vec<SearchResult>, String> {
+    let (text, typed) = parse_query(query)?;
+    let filters = filters.or(typed);
+    let terms = query_terms(&text);
+    if terms.is_empty() && filters.is_empty() {
+        return Ok(vec![]);
+    }
+    let section = filters.section.as_deref();
+    let paths = storage::all_entry_paths()?;
+    let mut hits = with_index(|index| {
+        let changed = index.refresh(&paths);
+        let ranked = if terms.is_empty() {
+            index.docs.keys().map(|id| (id.clone(), 0.0)).collect()
+        } else {
+            index.rank(&terms, section)
+        };
+        let hits: Vec<(IndexedDoc, f32)> = ranked
+            .into_iter()
+            .map(|(id, score)| (index.docs[&id].clone(), score))
+            .filter(|(doc, _)| filters.accepts(&doc.meta))
+            .collect();
+        (hits, changed)
+    })?;
+    if terms.is_empty() || filters.sort == Some(SearchSort::Date) {
+        hits.sort_by(|a, b| b.0.meta.id.cmp(&a.0.meta.id));
+    }
+
+    Ok(hits
+        .into_iter()
+        .map(|(doc, score)| SearchResult {
+            snippet: if terms.is_empty() {
+                None
+            } else {
+                storage::read_entry_file(&doc.path).ok().and_then(|e| snippet(&e, &terms, section))
+            },
+            entry: doc.meta,
+            score,
+        })
+        .collect())
+}
+
+/// Update the index after `entry` was written to `path`. Failures are only logged:
+/// the next search notices the changed file and indexes it then.
+pub fn index_entry(path: &Path, entry: &DiaryEntry) {
+    if let Err(e) = with_index(|index| {
+        index.index_entry(path, entry);
+        ((), true)
+    }) {
+        log::warn!("Failed to update search index: {}", e);
+    }
+}
+
+/// Drop a deleted entry from the index.
+pub fn remove_entry(id: &str) {
+    if let Err(e) = with_index(|index| ((), index.remove(id))) {
+        log::warn!("Failed to update search index: {}", e);
+    }
+}
+
+/// Drop the in-memory index; it is loaded from disk again on next use.
+pub fn unload() {
+    if let Ok(mut guard) = INDEX.lock() {
+        *guard = None;
+    }
+}
+
+/// Discard the index and index every entry again. Returns the number of entries indexed.
+pub fn rebuild() -> Result<usize, String> {
+    let paths = storage::all_entry_paths()?;
+    with_index(|index| {
+        *index = SearchIndex::default();
+        index.refresh(&paths);
+        (index.docs.len(), true)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support;
+
+    fn terms_of(text: &str) -> Vec<String> {
+        tokenize(text).into_iter().map(|t| t.term).collect()
+    }
+
+    fn entry(id: &str, title: &str, original: &str, translations: Vec<(&str, &str)>) -> DiaryEntry {
+        let mut entry = test_support::entry(id);
+        entry.meta.title = title.to_string();
+        entry.meta.mode = "translation".to_string();
+        entry.meta.languages = translations.iter().map(|(l, _)| l.to_string()).collect();
+        entry.original = original.to_string();
+        entry.translations = translations.into_iter().map(|(l, t)| (l.to_string(), t.to_string())).collect();
+        entry
+    }
+
+    fn index_of(entries: &[DiaryEntry]) -> SearchIndex {
+        let mut index = SearchIndex::default();
+        for e in entries {
+            index.index_entry(Path::new(&format!("/nonexistent/{}.md", e.meta.id)), e);
+        }
+        index
+    }
+
+    #[test]
+    fn test_tokenize_words_and_cjk() {
+        assert_eq!(terms_of("Went to Tokyo!"), vec!["went", "to", "tokyo"]);
+        assert_eq!(terms_of("東京へ"), vec!["東", "東京", "京", "京へ", "へ"]);
+        assert_eq!(terms_of("I ate 寿司."), vec!["i", "ate", "寿", "寿司", "司"]);
+        let tokens = tokenize("café 東京");
+        assert_eq!(&"café 東京"[tokens[0].start..tokens[0].end], "café");
+        assert_eq!(&"café 東京"[tokens[2].start..tokens[2].end], "東京");
+    }
+
+    #[test]
+    fn test_query_terms() {
+        let q = query_terms("東京タワー walk");
+        let texts: Vec<&str> = q.iter().map(|t| t.term.as_str()).collect();
+        assert_eq!(texts, vec!["東京", "京タ", "タワ", "ワー", "walk"]);
+        assert!(q.last().unwrap().prefix);
+        assert_eq!(query_terms("猫"), vec![QueryTerm { term: "猫".to_string(), prefix: false }]);
+        assert!(query_terms("  ... ").is_empty());
+    }
+
+    #[test]
+    fn test_rank_requires_all_terms_and_prefers_title() {
+        let index = index_of(&[
+            entry("2026-01-01_100000", "Park", "We walked in the park.", vec![]),
+            entry("2026-01-02_100000", "Morning", "A walk before work, then the park again.", vec![]),
+            entry("2026-01-03_100000", "Rain", "Stayed home.", vec![]),
+        ]);
+        let ranked = index.rank(&query_terms("park walk"), None);
+        let ids: Vec<&str> = ranked.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["2026-01-01_100000", "2026-01-02_100000"]);
+        assert!(index.rank(&query_terms("park rain"), None).is_empty());
+    }
+
+    #[test]
+    fn test_cjk_substring_search() {
+        let index = index_of(&[
+            entry("2026-02-01_100000", "日記", "今日は東京タワーに行きました。", vec![("en", "I went to Tokyo Tower today.")]),
+            entry("2026-02-02_100000", "日記", "京都に行った。", vec![]),
+        ]);
+        let ids = |q: &str| index.rank(&query_terms(q), None).into_iter().map(|(id, _)| id).collect::<Vec<_>>();
+        assert_eq!(ids("東京"), vec!["2026-02-01_100000"]);
+        assert_eq!(ids("京").len(), 2);
+        assert_eq!(ids("tower"), vec!["2026-02-01_100000"]);
+        // Frontmatter keys are not indexed
+        assert!(ids("mode").is_empty());
+    }
+
+    #[test]
+    fn test_rank_within_section() {
+        let index = index_of(&[
+            entry("2026-02-10_100000", "Pasta night", "Cooked dinner.", vec![("it", "Ho cucinato la pasta.")]),
+            entry("2026-02-11_100000", "Dinner", "We had pasta again.", vec![("it", "Abbiamo mangiato.")]),
+        ]);
+        let ids = |q: &str, section: &str| {
+            index.rank(&query_terms(q), Some(section)).into_iter().map(|(id, _)| id).collect::<Vec<_>>()
+        };
+        assert_eq!(ids("pasta", "title"), vec!["2026-02-10_100000"]);
+        assert_eq!(ids("pasta", "original"), vec!["2026-02-11_100000"]);
+        assert_eq!(ids("pasta", "it"), vec!["2026-02-10_100000"]);
+        assert!(ids("pasta dinner", "it").is_empty());
+    }
+
+    #[test]
+    fn test_parse_query_operators() {
+        let (text, filters) = parse_query("mode:correction  lang:it Tokyo from:2026-01-01 in:title sort:date 10:30").unwrap();
+        assert_eq!(text, "Tokyo 10:30");
+        assert_eq!(filters.mode.as_deref(), Some("correction"));
+        assert_eq!(filters.language.as_deref(), Some("it"));
+        assert_eq!(filters.section.as_deref(), Some("title"));
+        assert_eq!(filters.from, NaiveDate::from_ymd_opt(2026, 1, 1));
+        assert_eq!(filters.to, None);
+        assert_eq!(filters.sort, Some(SearchSort::Date));
+        assert!(parse_query("from:yesterday").is_err());
+        assert!(parse_query("sort:random").is_err());
+
+        let explicit = SearchFilters { mode: Some("translation".to_string()), ..Default::default() };
+        let merged = explicit.or(filters);
+        assert_eq!(merged.mode.as_deref(), Some("translation"));
+        assert_eq!(merged.language.as_deref(), Some("it"));
+    }
+
+    #[test]
+    fn test_filters_accept() {
+        let meta = list_item(&entry("2026-02-15_100000", "", "", vec![("it", "")]));
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok();
+        let filters = |from: &str, to: &str, mode: Option<&str>, lang: Option<&str>| SearchFilters {
+            from: date(from),
+            to: date(to),
+            mode: mode.map(str::to_string),
+            language: lang.map(str::to_string),
+            ..Default::default()
+        };
+        assert!(filters("2026-02-01", "2026-02-15", Some("translation"), Some("it")).accepts(&meta));
+        assert!(filters("", "", None, None).accepts(&meta));
+        assert!(!filters("2026-02-16", "", None, None).accepts(&meta));
+        assert!(!filters("", "2026-02-14", None, None).accepts(&meta));
+        assert!(!filters("", "", Some("correction"), None).accepts(&meta));
+        assert!(!filters("", "", None, Some("ja")).accepts(&meta));
+    }
+
+    #[test]
+    fn test_remove_and_reindex() {
+        let mut index = index_of(&[entry("2026-03-01_100000", "A", "apple pie", vec![])]);
+        assert_eq!(index.rank(&query_terms("apple"), None).len(), 1);
+        let updated = entry("2026-03-01_100000", "A", "banana bread", vec![]);
+        index.index_entry(Path::new("/nonexistent/2026-03-01_100000.md"), &updated);
+        assert!(index.rank(&query_terms("apple"), None).is_empty());
+        assert!(!index.postings.contains_key("apple"));
+        assert!(index.remove("2026-03-01_100000"));
+        assert!(index.postings.is_empty());
+    }
+
+    #[test]
+    fn test_snippet_highlights_matches() {
+        let e = entry(
+            "2026-04-01_100000",
+            "Trip",
+            "It rained all day, so we stayed inside and read books until the evening when it finally cleared.",
+            vec![("ja", "一日中雨が降った。")],
+        );
+        let s = snippet(&e, &query_terms("books"), None).unwrap();
+        assert_eq!(s.section, "original");
+        let highlighted: Vec<&str> = s.parts.iter().filter(|p| p.highlight).map(|p| p.text.as_str()).collect();
+        assert_eq!(highlighted, vec!["books"]);
+        assert_eq!(s.parts.first().unwrap().text, "…");
+
+        let s = snippet(&e, &query_terms("雨が"), None).unwrap();
+        assert_eq!(s.section, "ja");
+        assert_eq!(s.parts.iter().find(|p| p.highlight).unwrap().text, "雨が");
+    }
+}
@@ -0,0 +1,253 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use crate::atomic;
+use crate::config::config_path;
+use crate::crypto::{self, SecretKey, SALT_LEN};
+
+/// Secret names, one per provider credential.
+pub const CLAUDE_API_KEY: &str = "claude_api_key";
+pub const OPENAI_API_KEY: &str = "openai_api_key";
+
+const KEYCHAIN_SERVICE: &str = "com.diary-sensei.app";
+/// Fallback store beside config.json when there is no OS keychain.
+const SECRETS_FILE: &str = "secrets.enc";
+const FILE_MAGIC: &[u8] = b"DSSECRETS1";
+
+/// Where provider credentials are kept. The OS keychain (Secret Service on Linux)
+/// when one is reachable, otherwise a file encrypted with a passphrase.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretBackend {
+    Keychain,
+    EncryptedFile,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SecretStatus {
+    pub backend: SecretBackend,
+    /// Encrypted file only: the passphrase has not been entered this session.
+    pub locked: bool,
+    /// Encrypted file only: the file exists, so unlocking checks the passphrase
+    /// instead of choosing one.
+    pub initialized: bool,
+    pub claude_api_key: bool,
+    pub openai_api_key: bool,
+}
+
+// --- Encrypted file ---
+
+struct Unlocked {
+    key: SecretKey,
+    salt: [u8; SALT_LEN],
+}
+
+static UNLOCKED: Mutex<Option<Unlocked>> = Mutex::new(None);
+
+fn secrets_path() -> PathBuf {
+    config_path().with_file_name(SECRETS_FILE)
+}
+
+/// Split a secrets file into its salt and sealed contents.
+fn parse_file(data: &[u8]) -> Result<([u8; SALT_LEN], &[u8]), String> {
+    let rest = data
+        .strip_prefix(FILE_MAGIC)
+        .filter(|rest| rest.len() >= SALT_LEN)
+        .ok_or_else(|| "Secrets file is corrupted".to_string())?;
+    let (salt, sealed) = rest.split_at(SALT_LEN);
+    Ok((salt.try_into().unwrap(), sealed))
+}
+
+/// Create the file at `path` with no secrets in it, protected by `passphrase`.
+fn init_file(path: &Path, passphrase: &str) -> Result<Unlocked, String> {
+    if path.exists() {
+        return Err("The secret store already has a passphrase".to_string());
+    }
+    let salt = crypto::random_salt();
+    let unlocked = Unlocked { key: SecretKey::derive(passphrase, &salt)?, salt };
+    save_file(path, &unlocked, &BTreeMap::new())?;
+    Ok(unlocked)
+}
+
+/// Derive the key for the existing file at `path`, checking the passphrase.
+fn unlock_file(path: &Path, passphrase: &str) -> Result<Unlocked, String> {
+    if !path.exists() {
+        return Err("The secret store has no passphrase yet".to_string());
+    }
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    let (salt, sealed) = parse_file(&data)?;
+    let key = SecretKey::derive(passphrase, &salt)?;
+    crypto::open(&key, sealed)?;
+    Ok(Unlocked { key, salt })
+}
+
+fn load_file(path: &Path, unlocked: &Unlocked) -> Result<BTreeMap<String, String>, String> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    let (_, sealed) = parse_file(&data)?;
+    let json = crypto::open(&unlocked.key, sealed)?;
+    serde_json::from_slice(&json).map_err(|_| "Secrets file is corrupted".to_string())
+}
+
+fn save_file(path: &Path, unlocked: &Unlocked, secrets: &BTreeMap<String, String>) -> Result<(), String> {
+    let json = serde_json::to_vec(secrets).map_err(|e| e.to_string())?;
+    let mut data = FILE_MAGIC.to_vec();
+    data.extend_from_slice(&unlocked.salt);
+    data.extend(crypto::seal(&unlocked.key, &json)?);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    atomic::write_file(path, &data, false)
+}
+
+fn with_unlocked<T>(f: impl FnOnce(&Unlocked) -> Result<T, String>) -> Result<T, String> {
+    let guard = UNLOCKED.lock().map_err(|e| e.to_string())?;
+    let unlocked = guard
+        .as_ref()
+        .ok_or_else(|| "The secret store is locked. Enter your passphrase in Settings.".to_string())?;
+    f(unlocked)
+}
+
+// --- Keychain ---
+
+fn keychain_entry(name: &str) -> Result<keyring::Entry, keyring::Error> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, name)
+}
+
+/// Probed once per run: a missing entry still proves the keychain answers.
+fn keychain_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| match keychain_entry("availability-check").and_then(|e| e.get_password()) {
+        Ok(_) | Err(keyring::Error::NoEntry) => true,
+        Err(e) => {
+            log::warn!("OS keychain unavailable ({}), using the encrypted secrets file", e);
+            false
+        }
+    })
+}
+
+// --- Public API ---
+
+pub fn backend() -> SecretBackend {
+    if keychain_available() {
+        SecretBackend::Keychain
+    } else {
+        SecretBackend::EncryptedFile
+    }
+}
+
+/// The secret stored under `name`, if any.
+pub fn get(name: &str) -> Result<Option<String>, String> {
+    match backend() {
+        SecretBackend::Keychain => match keychain_entry(name).and_then(|e| e.get_password()) {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(format!("Cannot read {} from the keychain: {}", name, e)),
+        },
+        SecretBackend::EncryptedFile => {
+            with_unlocked(|unlocked| Ok(load_file(&secrets_path(), unlocked)?.remove(name)))
+        }
+    }
+}
+
+/// Store `value` under `name`; an empty value deletes the secret.
+pub fn set(name: &str, value: &str) -> Result<(), String> {
+    match backend() {
+        SecretBackend::Keychain => {
+            let entry = keychain_entry(name).map_err(|e| e.to_string())?;
+            let result = if value.is_empty() {
+                entry.delete_credential().or_else(|e| match e {
+                    keyring::Error::NoEntry => Ok(()),
+                    e => Err(e),
+                })
+            } else {
+                entry.set_password(value)
+            };
+            result.map_err(|e| format!("Cannot save {} to the keychain: {}", name, e))
+        }
+        SecretBackend::EncryptedFile => with_unlocked(|unlocked| {
+            let path = secrets_path();
+            let mut secrets = load_file(&path, unlocked)?;
+            if value.is_empty() {
+                secrets.remove(name);
+            } else {
+                secrets.insert(name.to_string(), value.to_string());
+            }
+            save_file(&path, unlocked, &secrets)
+        }),
+    }
+}
+
+/// Choose the passphrase of the encrypted secrets file and write the empty file.
+/// The passphrase is typed twice, as it cannot be recovered.
+pub fn init(passphrase: &str, confirmation: &str) -> Result<(), String> {
+    if passphrase.is_empty() {
+        return Err("Passphrase cannot be empty".to_string());
+    }
+    if passphrase != confirmation {
+        return Err("The passphrases do not match".to_string());
+    }
+    let unlocked = init_file(&secrets_path(), passphrase)?;
+    *UNLOCKED.lock().map_err(|e| e.to_string())? = Some(unlocked);
+    Ok(())
+}
+
+/// Enter the passphrase of the encrypted secrets file for this session. Fails
+/// until `init` has created the file.
+pub fn unlock(passphrase: &str) -> Result<(), String> {
+    if passphrase.is_empty() {
+        return Err("Passphrase cannot be empty".to_string());
+    }
+    let unlocked = unlock_file(&secrets_path(), passphrase)?;
+    *UNLOCKED.lock().map_err(|e| e.to_string())? = Some(unlocked);
+    Ok(())
+}
+
+pub fn status() -> SecretStatus {
+    let backend = backend();
+    let locked = backend == SecretBackend::EncryptedFile && UNLOCKED.lock().map_or(true, |u| u.is_none());
+    let has = |name: &str| !locked && get(name).is_ok_and(|v| v.is_some());
+    SecretStatus {
+        backend,
+        locked,
+        initialized: backend == SecretBackend::Keychain || secrets_path().exists(),
+        claude_api_key: has(CLAUDE_API_KEY),
+        openai_api_key: has(OPENAI_API_KEY),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::temp_dir;
+
+    #[test]
+    fn test_encrypted_file_round_trip() {
+        let dir = temp_dir("secrets");
+        let path = dir.join(SECRETS_FILE);
+
+        assert!(unlock_file(&path, "passphrase").is_err());
+        let unlocked = init_file(&path, "passphrase").unwrap();
+        assert!(path.exists());
+        assert!(load_file(&path, &unlocked).unwrap().is_empty());
+        assert!(init_file(&path, "other").is_err());
+        let secrets = BTreeMap::from([(CLAUDE_API_KEY.to_string(), "sk-ant-secret".to_string())]);
+        save_file(&path, &unlocked, &secrets).unwrap();
+
+        let raw = fs::read(&path).unwrap();
+        assert!(raw.starts_with(FILE_MAGIC));
+        assert!(!raw.windows(6).any(|w| w == b"sk-ant"));
+
+        let reopened = unlock_file(&path, "passphrase").unwrap();
+        assert_eq!(load_file(&path, &reopened).unwrap(), secrets);
+        assert!(unlock_file(&path, "wrong").is_err());
+
+        fs::write(&path, b"garbage").unwrap();
+        assert!(unlock_file(&path, "passphrase").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,9 +1,16 @@
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
-use crate::config::load_app_config;
-use chrono::Local;
+use std::path::{Path, PathBuf};
+use crate::claude::CorrectionEdit;
+use crate::config::{load_app_config, CorrectionStrength};
+use crate::entry_id::{sanitize_image_name, EntryId};
+use crate::history;
+use crate::trash;
+use crate::search;
+use crate::vault;
+use chrono::{Datelike, Local};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EntryMeta {
@@ -15,6 +22,10 @@ pub struct EntryMeta {
     pub date_format: Option<String>,
     pub created_at: Option<String>,  // ISO 8601: "2026-02-24T14:30:52"
     pub updated_at: Option<String>,  // ISO 8601: "2026-02-24T14:30:52"
+    #[serde(default)]
+    pub correction_strength: Option<CorrectionStrength>, // level used for the saved correction
+    #[serde(default)]
+    pub private: bool, // never sent to a remote AI provider
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +33,10 @@ pub struct DiaryEntry {
     pub meta: EntryMeta,
     pub original: String,
     pub translations: HashMap<String, String>, // lang_code -> result text
+    #[serde(default)]
+    pub explanations: HashMap<String, String>, // lang_code -> why the correction changed things
+    #[serde(default)]
+    pub edits: HashMap<String, Vec<CorrectionEdit>>, // lang_code -> structured edit list
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,14 +50,33 @@ pub struct EntryListItem {
     pub updated_at: Option<String>,
 }
 
-fn entries_dir() -> Result<PathBuf, String> {
-    let config = load_app_config();
+pub(crate) fn entries_dir() -> Result<PathBuf, String> {
+    let config = load_app_config()?;
     let home = dirs::home_dir()
         .ok_or_else(|| "Cannot determine home directory".to_string())?;
     let dir = config.entries_dir.replace('~', &home.to_string_lossy());
     Ok(PathBuf::from(dir))
 }
 
+/// Read a JSON data file kept beside the entries (vocabulary, review schedule).
+/// A missing file is the default value; an unreadable one is an error, so a later
+/// save cannot silently wipe the user's data. `what` names the file in errors.
+pub(crate) fn read_json_file<T: DeserializeOwned + Default>(path: &Path, what: &str) -> Result<T, String> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let content = vault::read_to_string(path)?;
+    serde_json::from_str(&content).map_err(|e| format!("{} is corrupted: {}", what, e))
+}
+
+pub(crate) fn write_json_file<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    vault::write(path, content.as_bytes(), true)
+}
+
 pub fn generate_entry_id(date: &str) -> String {
     let now = Local::now();
     format!("{}_{}", date, now.format("%H%M%S"))
@@ -56,6 +90,7 @@ pub fn list_entries_for_month(year: i32, month: u32) -> Result<Vec<EntryListItem
     if !dir.exists() {
         return Ok(vec![]);
     }
+    vault::ensure_unlocked(&dir)?;
 
     let mut entries = vec![];
     let read_dir = fs::read_dir(&dir).map_err(|e| e.to_string())?;
@@ -64,7 +99,7 @@ pub fn list_entries_for_month(year: i32, month: u32) -> Result<Vec<EntryListItem
         let entry = entry.map_err(|e| e.to_string())?;
         let path = entry.path();
         if path.extension().map_or(false, |ext| ext == "md") {
-            if let Ok(content) = fs::read_to_string(&path) {
+            if let Ok(content) = vault::read_to_string(&path) {
                 if let Some(mut meta) = parse_frontmatter(&content) {
                     // Old entries may not have an id field — derive from filename stem
                     if meta.id.is_empty() {
@@ -90,153 +125,177 @@ pub fn list_entries_for_month(year: i32, month: u32) -> Result<Vec<EntryListItem
     Ok(entries)
 }
 
-pub fn search_entries(query: &str) -> Result<Vec<EntryListItem>, String> {
+/// Paths of every entry file under `entries_dir`, in `YYYY/MM/*.md` layout.
+pub(crate) fn all_entry_paths() -> Result<Vec<PathBuf>, String> {
     let base = entries_dir()?;
+    let mut paths = vec![];
     if !base.exists() {
-        return Ok(vec![]);
+        return Ok(paths);
     }
-
-    let query_lower = query.to_lowercase();
-    let mut results = vec![];
-
-    // Walk through all year/month directories
-    let years = fs::read_dir(&base).map_err(|e| e.to_string())?;
-    for year_entry in years {
-        let year_entry = year_entry.map_err(|e| e.to_string())?;
+    vault::ensure_unlocked(&base)?;
+    for year_entry in fs::read_dir(&base).map_err(|e| e.to_string())?.flatten() {
         let year_path = year_entry.path();
-        if !year_path.is_dir() { continue; }
-
-        let months = match fs::read_dir(&year_path) {
-            Ok(m) => m,
-            Err(_) => continue,
-        };
-        for month_entry in months {
-            let month_entry = month_entry.map_err(|e| e.to_string())?;
+        // Skip hidden directories such as the revision history
+        if !year_path.is_dir() || year_entry.file_name().to_string_lossy().starts_with('.') { continue; }
+        let Ok(months) = fs::read_dir(&year_path) else { continue };
+        for month_entry in months.flatten() {
             let month_path = month_entry.path();
             if !month_path.is_dir() { continue; }
+            let Ok(files) = fs::read_dir(&month_path) else { continue };
+            paths.extend(
+                files
+                    .flatten()
+                    .map(|f| f.path())
+                    .filter(|p| p.extension().is_some_and(|ext| ext == "md")),
+            );
+        }
+    }
+    Ok(paths)
+}
 
-            let files = match fs::read_dir(&month_path) {
-                Ok(f) => f,
-                Err(_) => continue,
-            };
-            for file_entry in files {
-                let file_entry = file_entry.map_err(|e| e.to_string())?;
-                let path = file_entry.path();
-                if !path.extension().map_or(false, |ext| ext == "md") { continue; }
-
-                if let Ok(content) = fs::read_to_string(&path) {
-                    // Check if title or body contains the query
-                    if content.to_lowercase().contains(&query_lower) {
-                        if let Some(mut meta) = parse_frontmatter(&content) {
-                            if meta.id.is_empty() {
-                                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                                    meta.id = stem.to_string();
-                                }
-                            }
-                            results.push(EntryListItem {
-                                id: meta.id,
-                                date: meta.date,
-                                title: meta.title,
-                                mode: meta.mode,
-                                languages: meta.languages,
-                                created_at: meta.created_at,
-                                updated_at: meta.updated_at,
-                            });
-                        }
-                    }
-                }
-            }
+/// Parse the entry file at `path`.
+pub(crate) fn read_entry_file(path: &Path) -> Result<DiaryEntry, String> {
+    let content = vault::read_to_string(path)?;
+    let mut entry = parse_entry(&content)?;
+    // Old entries may not have an id field — derive from filename stem
+    if entry.meta.id.is_empty() {
+        if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+            entry.meta.id = stem.to_string();
         }
     }
+    Ok(entry)
+}
 
-    results.sort_by(|a, b| b.id.cmp(&a.id));
-    Ok(results)
+/// Parse every entry on disk. Unreadable or malformed files are skipped.
+pub fn read_all_entries() -> Result<Vec<DiaryEntry>, String> {
+    let mut entries: Vec<DiaryEntry> = all_entry_paths()?
+        .iter()
+        .filter_map(|path| read_entry_file(path).ok())
+        .collect();
+    entries.sort_by(|a, b| b.meta.id.cmp(&a.meta.id));
+    Ok(entries)
 }
 
-pub fn read_entry_by_id(id: &str) -> Result<DiaryEntry, String> {
-    // id format: "2026-02-24_143052"
-    // date is the first 10 characters
-    if id.len() < 10 {
-        return Err("Invalid id format".to_string());
-    }
-    let date = &id[..10];
-    let parts: Vec<&str> = date.split('-').collect();
-    if parts.len() != 3 {
-        return Err("Invalid date in id".to_string());
-    }
+/// Entries dated `from` through `to` (inclusive, "YYYY-MM-DD"), oldest first.
+/// Files in the range that cannot be read or parsed are skipped; their file
+/// names are returned alongside the entries so the caller can report them.
+pub fn read_entries_in_range(from: &str, to: &str) -> Result<(Vec<DiaryEntry>, Vec<String>), String> {
+    read_entries_in_range_in(&entries_dir()?, from, to)
+}
 
-    let path = entries_dir()?
-        .join(parts[0])
-        .join(parts[1])
-        .join(format!("{}.md", id));
+fn read_entries_in_range_in(base: &Path, from: &str, to: &str) -> Result<(Vec<DiaryEntry>, Vec<String>), String> {
+    let parse = |d: &str| {
+        chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").map_err(|_| format!("Invalid date: {}", d))
+    };
+    let (from, to) = (parse(from)?, parse(to)?);
+    if from > to {
+        return Err("Start date is after end date".to_string());
+    }
+    let in_range = |d: &str| parse(d).is_ok_and(|d| d >= from && d <= to);
+
+    let (mut entries, mut skipped) = (vec![], vec![]);
+    let (mut year, mut month) = (from.year(), from.month());
+    while (year, month) <= (to.year(), to.month()) {
+        let dir = base.join(format!("{:04}", year)).join(format!("{:02}", month));
+        (year, month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+        if !dir.exists() {
+            continue;
+        }
+        vault::ensure_unlocked(&dir)?;
+        for file in fs::read_dir(&dir).map_err(|e| e.to_string())?.flatten() {
+            let path = file.path();
+            if !path.extension().is_some_and(|ext| ext == "md") {
+                continue;
+            }
+            match read_entry_file(&path) {
+                Ok(entry) => {
+                    if in_range(&entry.meta.date) {
+                        entries.push(entry);
+                    }
+                }
+                Err(e) => {
+                    // Entry files are named after their id, which starts with the date
+                    let name = file.file_name().to_string_lossy().into_owned();
+                    if name.get(..10).map_or(true, in_range) {
+                        log::warn!("Skipping unreadable entry {}: {}", name, e);
+                        skipped.push(name);
+                    }
+                }
+            }
+        }
+    }
+    entries.sort_by(|a, b| a.meta.id.cmp(&b.meta.id));
+    skipped.sort();
+    Ok((entries, skipped))
+}
 
+pub fn read_entry_by_id(id: &EntryId) -> Result<DiaryEntry, String> {
+    let path = id.month_dir(&entries_dir()?).join(format!("{}.md", id));
     if !path.exists() {
         return Err(format!("Entry not found: {}", id));
     }
 
-    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let content = vault::read_to_string(&path)?;
     parse_entry(&content)
 }
 
 pub fn save_entry_to_disk(entry: &DiaryEntry) -> Result<(), String> {
-    let id = &entry.meta.id;
-    let date = &entry.meta.date;
-    let parts: Vec<&str> = date.split('-').collect();
-    if parts.len() != 3 {
-        return Err("Invalid date format".to_string());
-    }
+    let id = EntryId::parse(&entry.meta.id)?;
+    let date = chrono::NaiveDate::parse_from_str(&entry.meta.date, "%Y-%m-%d")
+        .map_err(|_| "Invalid date format".to_string())?;
 
-    let dir = entries_dir()?.join(parts[0]).join(parts[1]);
+    let dir = entries_dir()?
+        .join(format!("{:04}", date.year()))
+        .join(format!("{:02}", date.month()));
     fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
 
     let path = dir.join(format!("{}.md", id));
     let content = serialize_entry(entry);
-    fs::write(&path, content).map_err(|e| e.to_string())
+    vault::write(&path, content.as_bytes(), true)?;
+    search::index_entry(&path, entry);
+    if let Err(e) = history::record(&id, &content) {
+        log::warn!("Failed to record revision of {}: {}", id, e);
+    }
+    Ok(())
 }
 
-pub fn save_image_to_disk(id: &str, filename: &str, data: &[u8]) -> Result<String, String> {
-    if id.len() < 10 {
-        return Err("Invalid id format".to_string());
-    }
-    let date = &id[..10];
-    let parts: Vec<&str> = date.split('-').collect();
-    if parts.len() != 3 {
-        return Err("Invalid date in id".to_string());
+/// Whether entry `id` is marked `private: true`. An entry not saved yet is not.
+pub fn is_entry_private(id: &EntryId) -> Result<bool, String> {
+    let path = id.month_dir(&entries_dir()?).join(format!("{}.md", id));
+    if !path.exists() {
+        return Ok(false);
     }
+    Ok(read_entry_file(&path)?.meta.private)
+}
 
-    let img_dir = entries_dir()?
-        .join(parts[0])
-        .join(parts[1])
-        .join(id);
+/// Store an image in the entry's image folder under a sanitized version of `filename`.
+/// Returns the Markdown-relative path.
+pub fn save_image_to_disk(id: &EntryId, filename: &str, data: &[u8]) -> Result<String, String> {
+    let filename = sanitize_image_name(filename)?;
+    let img_dir = id.month_dir(&entries_dir()?).join(id.as_str());
     fs::create_dir_all(&img_dir).map_err(|e| e.to_string())?;
 
-    let path = img_dir.join(filename);
-    fs::write(&path, data).map_err(|e| e.to_string())?;
+    let path = img_dir.join(&filename);
+    vault::write(&path, data, false)?;
 
     Ok(format!("./{}/{}", id, filename))
 }
 
-pub fn delete_entry_from_disk(id: &str) -> Result<(), String> {
-    if id.len() < 10 {
-        return Err("Invalid id format".to_string());
-    }
-    let date = &id[..10];
-    let parts: Vec<&str> = date.split('-').collect();
-    if parts.len() != 3 {
-        return Err("Invalid date in id".to_string());
-    }
-
-    let dir = entries_dir()?.join(parts[0]).join(parts[1]);
-    let md_path = dir.join(format!("{}.md", id));
-    let img_dir = dir.join(id);
-
-    if md_path.exists() {
-        fs::remove_file(&md_path).map_err(|e| e.to_string())?;
-    }
-    if img_dir.exists() {
-        fs::remove_dir_all(&img_dir).map_err(|e| e.to_string())?;
+/// Read back an image saved with `save_image_to_disk`, decrypted if the diary is
+/// encrypted.
+pub fn read_image_from_disk(id: &EntryId, filename: &str) -> Result<Vec<u8>, String> {
+    let filename = sanitize_image_name(filename)?;
+    let path = id.month_dir(&entries_dir()?).join(id.as_str()).join(&filename);
+    if !path.exists() {
+        return Err(format!("Image not found: {}", filename));
     }
+    vault::read(&path)
+}
 
+/// Move an entry and its images to the trash; see `trash::restore` to bring it back.
+pub fn delete_entry_from_disk(id: &EntryId) -> Result<(), String> {
+    trash::move_to_trash(&id.month_dir(&entries_dir()?), id)?;
+    search::remove_entry(id.as_str());
     Ok(())
 }
 
@@ -259,6 +318,8 @@ fn parse_frontmatter(content: &str) -> Option<EntryMeta> {
     let mut date_format = None;
     let mut created_at = None;
     let mut updated_at = None;
+    let mut correction_strength = None;
+    let mut private = false;
 
     for line in yaml.lines() {
         let line = line.trim();
@@ -286,6 +347,8 @@ fn parse_frontmatter(content: &str) -> Option<EntryMeta> {
                 "created_at" => created_at = Some(value.to_string()),
                 "updated_at" => updated_at = Some(value.to_string()),
                 "language" => language = value.to_string(),
+                "correction_strength" => correction_strength = CorrectionStrength::parse(value),
+                "private" => private = value == "true",
                 "languages" => {
                     // Parse YAML inline list: [ja, en] or [ja]
                     let trimmed = value.trim().trim_start_matches('[').trim_end_matches(']');
@@ -314,6 +377,8 @@ fn parse_frontmatter(content: &str) -> Option<EntryMeta> {
         date_format,
         created_at,
         updated_at,
+        correction_strength,
+        private,
     })
 }
 
@@ -355,13 +420,50 @@ fn parse_entry(content: &str) -> Result<DiaryEntry, String> {
         }
     }
 
+    let mut explanations = HashMap::new();
+    let mut edits = HashMap::new();
+    for lang_code in &meta.languages {
+        let explanation = extract_section(body, &explanation_header(lang_code));
+        if !explanation.is_empty() {
+            explanations.insert(lang_code.clone(), explanation);
+        }
+        if let Some(edit_list) = parse_edits_block(&extract_section(body, &edits_header(lang_code))) {
+            edits.insert(lang_code.clone(), edit_list);
+        }
+    }
+
     Ok(DiaryEntry {
         meta,
         original,
         translations,
+        explanations,
+        edits,
     })
 }
 
+fn explanation_header(lang_code: &str) -> String {
+    format!("# Explanation: {}", lang_code)
+}
+
+fn edits_header(lang_code: &str) -> String {
+    format!("# Edits: {}", lang_code)
+}
+
+/// Edits are stored as a fenced JSON array; `[]` records a correction that found
+/// nothing. A section that is missing or was hand-edited into invalid JSON yields
+/// None rather than failing the whole entry.
+fn parse_edits_block(section: &str) -> Option<Vec<CorrectionEdit>> {
+    let json = section
+        .trim()
+        .trim_start_matches("```json")
+        .trim_end_matches("```")
+        .trim();
+    if json.is_empty() {
+        return None;
+    }
+    serde_json::from_str(json).ok()
+}
+
 /// Extract text under a section header up to the next `# ` heading or end of body.
 fn extract_section(body: &str, header: &str) -> String {
     let Some(start_idx) = body.find(header) else {
@@ -419,6 +521,14 @@ pub fn serialize_entry(entry: &DiaryEntry) -> String {
         .map(|t| format!("updated_at: {}\n", t))
         .unwrap_or_default();
 
+    let correction_strength_line = entry
+        .meta
+        .correction_strength
+        .map(|s| format!("correction_strength: {}\n", s.as_str()))
+        .unwrap_or_default();
+
+    let private_line = if entry.meta.private { "private: true\n" } else { "" };
+
     let languages_str = format!(
         "[{}]",
         entry.meta.languages.join(", ")
@@ -428,7 +538,7 @@ pub fn serialize_entry(entry: &DiaryEntry) -> String {
     let escaped_title = entry.meta.title.replace('\\', "\\\\").replace('"', "\\\"");
 
     let mut output = format!(
-        "---\nid: {}\ntitle: \"{}\"\ndate: {}\nmode: {}\nlanguages: {}\n{}{}{}---\n\n# Original\n\n{}\n",
+        "---\nid: {}\ntitle: \"{}\"\ndate: {}\nmode: {}\nlanguages: {}\n{}{}{}{}{}---\n\n# Original\n\n{}\n",
         entry.meta.id,
         escaped_title,
         entry.meta.date,
@@ -437,37 +547,83 @@ pub fn serialize_entry(entry: &DiaryEntry) -> String {
         date_format_line,
         created_at_line,
         updated_at_line,
+        correction_strength_line,
+        private_line,
         entry.original,
     );
 
     for lang_code in &entry.meta.languages {
         let text = entry.translations.get(lang_code).map(|s| s.as_str()).unwrap_or("");
         output.push_str(&format!("\n# {}\n\n{}\n", lang_code, text));
+
+        if let Some(explanation) = entry.explanations.get(lang_code).filter(|e| !e.trim().is_empty()) {
+            output.push_str(&format!("\n{}\n\n{}\n", explanation_header(lang_code), explanation.trim()));
+        }
+        if let Some(edits) = entry.edits.get(lang_code) {
+            // JSON strings escape newlines, so the block can never contain a "# " heading line
+            let json = serde_json::to_string_pretty(edits).unwrap_or_default();
+            output.push_str(&format!("\n{}\n\n```json\n{}\n```\n", edits_header(lang_code), json));
+        }
     }
 
     output
 }
 
+/// Fixtures shared by the test modules.
 #[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn make_entry(title: &str, original: &str, languages: Vec<&str>, translations: Vec<(&str, &str)>) -> DiaryEntry {
+pub(crate) mod test_support {
+    use super::{DiaryEntry, EntryMeta};
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// An empty scratch directory, unique to `name` and this test run.
+    pub(crate) fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("diary-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A blank correction entry; the date is taken from the id.
+    pub(crate) fn entry(id: &str) -> DiaryEntry {
         DiaryEntry {
             meta: EntryMeta {
-                id: "2026-02-24_143052".to_string(),
-                date: "2026-02-24".to_string(),
-                title: title.to_string(),
+                id: id.to_string(),
+                date: id[..10].to_string(),
+                title: String::new(),
                 mode: "correction".to_string(),
-                languages: languages.into_iter().map(String::from).collect(),
+                languages: vec![],
                 date_format: None,
-                created_at: Some("2026-02-24T14:30:52".to_string()),
-                updated_at: Some("2026-02-24T14:35:00".to_string()),
+                created_at: None,
+                updated_at: None,
+                correction_strength: None,
+                private: false,
             },
-            original: original.to_string(),
-            translations: translations.into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            original: String::new(),
+            translations: HashMap::new(),
+            explanations: HashMap::new(),
+            edits: HashMap::new(),
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::test_support::temp_dir;
+    use crate::mistakes::MistakeCategory;
+
+    fn make_entry(title: &str, original: &str, languages: Vec<&str>, translations: Vec<(&str, &str)>) -> DiaryEntry {
+        let mut entry = test_support::entry("2026-02-24_143052");
+        entry.meta.title = title.to_string();
+        entry.meta.languages = languages.into_iter().map(String::from).collect();
+        entry.meta.created_at = Some("2026-02-24T14:30:52".to_string());
+        entry.meta.updated_at = Some("2026-02-24T14:35:00".to_string());
+        entry.original = original.to_string();
+        entry.translations = translations.into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        entry
+    }
 
     // --- parse_frontmatter tests ---
 
@@ -592,6 +748,78 @@ mod tests {
         assert_eq!(parsed.translations.get("it").unwrap(), "Testo italiano");
     }
 
+    #[test]
+    fn test_round_trip_correction_strength() {
+        let mut entry = make_entry("Hints", "Io sono andato", vec!["it"], vec![("it", "Io sono andato")]);
+        entry.meta.correction_strength = Some(CorrectionStrength::Hint);
+        let serialized = serialize_entry(&entry);
+        assert!(serialized.contains("correction_strength: hint\n"));
+        let parsed = parse_entry(&serialized).unwrap();
+        assert_eq!(parsed.meta.correction_strength, Some(CorrectionStrength::Hint));
+    }
+
+    #[test]
+    fn test_round_trip_private_flag() {
+        let mut entry = make_entry("Secret", "Body", vec!["ja"], vec![("ja", "本文")]);
+        assert!(!serialize_entry(&entry).contains("private"));
+        entry.meta.private = true;
+        let serialized = serialize_entry(&entry);
+        assert!(serialized.contains("private: true\n"));
+        assert!(parse_entry(&serialized).unwrap().meta.private);
+        assert!(!parse_frontmatter("---\nid: x\nprivate: no\n---\n").unwrap().private);
+    }
+
+    #[test]
+    fn test_missing_correction_strength_is_none() {
+        let entry = make_entry("Plain", "Body", vec!["ja"], vec![("ja", "本文")]);
+        let serialized = serialize_entry(&entry);
+        assert!(!serialized.contains("correction_strength"));
+        assert_eq!(parse_entry(&serialized).unwrap().meta.correction_strength, None);
+    }
+
+    #[test]
+    fn test_round_trip_explanation_and_edits() {
+        let mut entry = make_entry("Park", "I goed to park", vec!["en", "ja"], vec![("en", "I went to the park"), ("ja", "公園に行った")]);
+        entry.explanations.insert("en".to_string(), "1. \"goed\" → \"went\" — irregular past tense".to_string());
+        entry.edits.insert(
+            "en".to_string(),
+            vec![CorrectionEdit {
+                original: "goed".to_string(),
+                replacement: "went".to_string(),
+                category: MistakeCategory::Tense,
+                reason: "irregular past tense\n# not a heading".to_string(),
+            }],
+        );
+        let serialized = serialize_entry(&entry);
+        assert!(serialized.contains("# Explanation: en\n\n1. \"goed\""));
+        assert!(serialized.contains("# Edits: en\n\n```json\n"));
+        assert!(!serialized.contains("# Explanation: ja"));
+        assert!(!serialized.contains("# Edits: ja"));
+
+        let parsed = parse_entry(&serialized).unwrap();
+        assert_eq!(parsed.translations.get("en").unwrap(), "I went to the park");
+        assert_eq!(parsed.translations.get("ja").unwrap(), "公園に行った");
+        assert_eq!(parsed.explanations, entry.explanations);
+        assert_eq!(parsed.edits, entry.edits);
+    }
+
+    #[test]
+    fn test_round_trip_clean_correction() {
+        let mut entry = make_entry("Park", "I went to the park", vec!["en"], vec![("en", "I went to the park")]);
+        entry.edits.insert("en".to_string(), vec![]);
+        let serialized = serialize_entry(&entry);
+        assert!(serialized.contains("# Edits: en\n\n```json\n[]\n```\n"));
+        assert_eq!(parse_entry(&serialized).unwrap().edits, entry.edits);
+    }
+
+    #[test]
+    fn test_invalid_edits_block_is_ignored() {
+        let content = "---\nid: test_010\ntitle: \"T\"\ndate: 2026-02-24\nmode: correction\nlanguages: [en]\n---\n\n# Original\n\nHi\n\n# en\n\nHello\n\n# Edits: en\n\n```json\n[{broken\n```\n";
+        let parsed = parse_entry(content).unwrap();
+        assert_eq!(parsed.translations.get("en").unwrap(), "Hello");
+        assert!(parsed.edits.is_empty());
+    }
+
     // --- extract_section tests ---
 
     #[test]
@@ -626,4 +854,37 @@ mod tests {
         let time_part = &id[11..];
         assert!(time_part.chars().all(|c| c.is_ascii_digit()));
     }
+
+    // --- path hardening tests ---
+
+    #[test]
+    fn test_save_rejects_hostile_id_and_date() {
+        // Both are checked before the entries directory is even resolved
+        let mut entry = make_entry("T", "x", vec![], vec![]);
+        entry.meta.id = "2026-02-24/../../x".to_string();
+        assert!(save_entry_to_disk(&entry).unwrap_err().contains("Invalid entry id"));
+
+        let mut entry = make_entry("T", "x", vec![], vec![]);
+        entry.meta.date = "../../etc-x-y".to_string();
+        assert_eq!(save_entry_to_disk(&entry).unwrap_err(), "Invalid date format");
+    }
+
+    #[test]
+    fn test_range_skips_unreadable_entries() {
+        let base = temp_dir("range");
+        let month = base.join("2026").join("02");
+        fs::create_dir_all(&month).unwrap();
+        for id in ["2026-02-10_090000", "2026-02-20_090000", "2026-02-27_090000"] {
+            fs::write(month.join(format!("{}.md", id)), serialize_entry(&test_support::entry(id))).unwrap();
+        }
+        fs::write(month.join("2026-02-15_090000.md"), "not an entry").unwrap();
+        fs::write(month.join("2026-02-28_090000.md"), "not an entry").unwrap();
+
+        let (entries, skipped) = read_entries_in_range_in(&base, "2026-02-01", "2026-02-25").unwrap();
+        let ids: Vec<&str> = entries.iter().map(|e| e.meta.id.as_str()).collect();
+        assert_eq!(ids, vec!["2026-02-10_090000", "2026-02-20_090000"]);
+        // The corrupt file outside the range is not reported
+        assert_eq!(skipped, vec!["2026-02-15_090000.md"]);
+        fs::remove_dir_all(&base).unwrap();
+    }
 }
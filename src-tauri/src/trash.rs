@@ -0,0 +1,258 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::NaiveDateTime;
+use crate::atomic;
+use crate::config::load_app_config;
+use crate::entry_id::EntryId;
+use crate::history;
+use crate::search;
+use crate::storage::{self, DiaryEntry};
+use crate::vault;
+
+/// Deleted entries are moved to `{entries_dir}/.trash/{entry id}/`, keeping the
+/// Markdown file, its backup and the image folder under their original names.
+const TRASH_DIR: &str = ".trash";
+const INFO_FILE: &str = "trashed.json";
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrashItem {
+    pub id: String,
+    pub date: String,
+    pub title: String,
+    pub deleted_at: String, // ISO 8601
+}
+
+/// Names of the files and folders that make up entry `id` in its month directory.
+fn entry_parts(id: &str) -> [String; 3] {
+    let md = format!("{}.md", id);
+    let backup = atomic::backup_path(Path::new(&md)).to_string_lossy().to_string();
+    [md, backup, id.to_string()]
+}
+
+fn move_parts(from: &Path, to: &Path, id: &str) -> Result<(), String> {
+    for name in entry_parts(id) {
+        let source = from.join(&name);
+        if source.exists() {
+            fs::rename(&source, to.join(&name)).map_err(|e| format!("Failed to move {}: {}", name, e))?;
+        }
+    }
+    Ok(())
+}
+
+fn trash_in(month_dir: &Path, trash_root: &Path, id: &str, now: NaiveDateTime) -> Result<(), String> {
+    let md_path = month_dir.join(format!("{}.md", id));
+    if !entry_parts(id).iter().any(|name| month_dir.join(name).exists()) {
+        return Ok(());
+    }
+    let (date, title) = match storage::read_entry_file(&md_path) {
+        Ok(entry) => (entry.meta.date, entry.meta.title),
+        Err(_) => (id.get(..10).unwrap_or(id).to_string(), String::new()),
+    };
+
+    let dest = trash_root.join(id);
+    if dest.exists() {
+        fs::remove_dir_all(&dest).map_err(|e| e.to_string())?;
+    }
+    fs::create_dir_all(&dest).map_err(|e| e.to_string())?;
+    let info = TrashItem {
+        id: id.to_string(),
+        date,
+        title,
+        deleted_at: now.format(TIME_FORMAT).to_string(),
+    };
+    storage::write_json_file(&dest.join(INFO_FILE), &info)?;
+    move_parts(month_dir, &dest, id)
+}
+
+fn restore_in(month_dir: &Path, trash_root: &Path, id: &str) -> Result<PathBuf, String> {
+    let source = trash_root.join(id);
+    if !source.join(INFO_FILE).exists() {
+        return Err(format!("Entry not in trash: {}", id));
+    }
+    let md_path = month_dir.join(format!("{}.md", id));
+    if md_path.exists() {
+        return Err(format!("An entry with id {} already exists", id));
+    }
+    fs::create_dir_all(month_dir).map_err(|e| e.to_string())?;
+    move_parts(&source, month_dir, id)?;
+    fs::remove_dir_all(&source).map_err(|e| e.to_string())?;
+    Ok(md_path)
+}
+
+fn list_in(trash_root: &Path) -> Vec<TrashItem> {
+    let Ok(dirs) = fs::read_dir(trash_root) else { return vec![] };
+    let mut items: Vec<TrashItem> = dirs
+        .flatten()
+        .filter_map(|dir| {
+            let path = dir.path().join(INFO_FILE);
+            match storage::read_json_file::<Option<TrashItem>>(&path, "Trash info") {
+                // The id is used as a path later, so it must be the folder's own valid name
+                Ok(Some(item)) if EntryId::parse(&item.id).is_ok() && dir.file_name() == item.id.as_str() => Some(item),
+                Ok(_) => None,
+                Err(e) => {
+                    log::warn!("{}", e);
+                    None
+                }
+            }
+        })
+        .collect();
+    items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at).then_with(|| b.id.cmp(&a.id)));
+    items
+}
+
+/// Permanently delete trashed entries deleted before `cutoff`, or all of them.
+/// Returns the ids removed.
+fn purge_in(trash_root: &Path, cutoff: Option<NaiveDateTime>) -> Result<Vec<String>, String> {
+    let cutoff = cutoff.map(|c| c.format(TIME_FORMAT).to_string());
+    let mut purged = vec![];
+    for item in list_in(trash_root) {
+        if cutoff.as_ref().map_or(true, |c| item.deleted_at < *c) {
+            fs::remove_dir_all(trash_root.join(&item.id)).map_err(|e| e.to_string())?;
+            purged.push(item.id);
+        }
+    }
+    Ok(purged)
+}
+
+// --- Public API ---
+
+fn trash_root() -> Result<PathBuf, String> {
+    let root = storage::entries_dir()?.join(TRASH_DIR);
+    // Trash info files are encrypted in a vault, and unreadable ones would be skipped
+    vault::ensure_unlocked(&root)?;
+    Ok(root)
+}
+
+fn now() -> NaiveDateTime {
+    chrono::Local::now().naive_local()
+}
+
+/// Move entry `id` (file, backup and images) from `month_dir` to the trash.
+pub fn move_to_trash(month_dir: &Path, id: &EntryId) -> Result<(), String> {
+    trash_in(month_dir, &trash_root()?, id.as_str(), now())
+}
+
+/// Move entry `id` back to where it was deleted from.
+pub fn restore(id: &EntryId) -> Result<DiaryEntry, String> {
+    let path = restore_in(&id.month_dir(&storage::entries_dir()?), &trash_root()?, id.as_str())?;
+    let entry = storage::read_entry_file(&path)?;
+    search::index_entry(&path, &entry);
+    Ok(entry)
+}
+
+/// Trashed entries, most recently deleted first.
+pub fn list() -> Result<Vec<TrashItem>, String> {
+    Ok(list_in(&trash_root()?))
+}
+
+fn forget(ids: &[String]) {
+    for id in ids.iter().filter_map(|id| EntryId::parse(id).ok()) {
+        if let Err(e) = history::remove_all(&id) {
+            log::warn!("Failed to remove revisions of {}: {}", id, e);
+        }
+    }
+}
+
+/// Permanently delete everything in the trash, with its revision history.
+/// Returns the number of entries removed.
+pub fn empty() -> Result<usize, String> {
+    let purged = purge_in(&trash_root()?, None)?;
+    forget(&purged);
+    Ok(purged.len())
+}
+
+/// Permanently delete entries trashed longer ago than `trash_retention_days`.
+pub fn purge_expired() -> Result<usize, String> {
+    let days = load_app_config()?.trash_retention_days;
+    if days == 0 {
+        return Ok(0);
+    }
+    let purged = purge_in(&trash_root()?, Some(now() - chrono::Duration::days(days as i64)))?;
+    forget(&purged);
+    Ok(purged.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::temp_dir;
+
+    const ID: &str = "2026-06-01_100000";
+
+    fn at(time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(time, TIME_FORMAT).unwrap()
+    }
+
+    fn setup(name: &str) -> (PathBuf, PathBuf, PathBuf) {
+        let base = temp_dir(&format!("trash-{}", name));
+        let month = base.join("2026").join("06");
+        fs::create_dir_all(month.join(ID)).unwrap();
+        fs::write(
+            month.join(format!("{}.md", ID)),
+            format!("---\nid: {}\ndate: 2026-06-01\ntitle: Picnic\nmode: translation\n---\n\nWe ate outside.\n", ID),
+        )
+        .unwrap();
+        fs::write(month.join(format!("{}.md.bak", ID)), "old").unwrap();
+        fs::write(month.join(ID).join("photo.png"), [1, 2, 3]).unwrap();
+        (base.clone(), month, base.join(TRASH_DIR))
+    }
+
+    #[test]
+    fn test_trash_and_restore_round_trip() {
+        let (base, month, trash) = setup("roundtrip");
+        trash_in(&month, &trash, ID, at("2026-06-02T09:00:00")).unwrap();
+        assert!(!month.join(format!("{}.md", ID)).exists());
+        assert!(!month.join(ID).exists());
+        assert!(trash.join(ID).join(ID).join("photo.png").exists());
+
+        let items = list_in(&trash);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, "Picnic");
+        assert_eq!(items[0].deleted_at, "2026-06-02T09:00:00");
+
+        let path = restore_in(&month, &trash, ID).unwrap();
+        assert!(path.exists());
+        assert_eq!(fs::read_to_string(month.join(format!("{}.md.bak", ID))).unwrap(), "old");
+        assert!(month.join(ID).join("photo.png").exists());
+        assert!(list_in(&trash).is_empty());
+        assert!(restore_in(&month, &trash, ID).is_err());
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn test_restore_refuses_to_overwrite() {
+        let (base, month, trash) = setup("overwrite");
+        trash_in(&month, &trash, ID, at("2026-06-02T09:00:00")).unwrap();
+        fs::write(month.join(format!("{}.md", ID)), "new entry").unwrap();
+        assert!(restore_in(&month, &trash, ID).is_err());
+        assert_eq!(list_in(&trash).len(), 1);
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn test_list_ignores_tampered_info() {
+        let (base, month, trash) = setup("tampered");
+        trash_in(&month, &trash, ID, at("2026-06-02T09:00:00")).unwrap();
+        let mut item = list_in(&trash).remove(0);
+        item.id = "../../victim".to_string();
+        storage::write_json_file(&trash.join(ID).join(INFO_FILE), &item).unwrap();
+        assert!(list_in(&trash).is_empty());
+        assert!(purge_in(&trash, None).unwrap().is_empty());
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn test_purge_by_age() {
+        let (base, month, trash) = setup("purge");
+        trash_in(&month, &trash, ID, at("2026-06-02T09:00:00")).unwrap();
+        assert!(purge_in(&trash, Some(at("2026-06-02T08:00:00"))).unwrap().is_empty());
+        assert_eq!(purge_in(&trash, Some(at("2026-07-02T09:00:00"))).unwrap(), vec![ID]);
+        assert!(!trash.join(ID).exists());
+        // Deleting an entry that is already gone is a no-op
+        trash_in(&month, &trash, ID, at("2026-06-03T09:00:00")).unwrap();
+        assert!(list_in(&trash).is_empty());
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -0,0 +1,269 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::atomic;
+use crate::crypto::{self, SecretKey, SALT_LEN};
+use crate::search;
+use crate::storage;
+
+/// Optional encryption of everything under `entries_dir`: entries, images, history,
+/// trash and data files. A vault is marked by a key file at the root holding the
+/// salt and a sealed check value; each file is then stored as `FILE_MAGIC` followed
+/// by its sealed contents, under its usual name. Reads tell the two formats apart,
+/// so a folder that is half-way through migration stays readable.
+const VAULT_FILE: &str = ".vault";
+const VAULT_MAGIC: &[u8] = b"DSVAULTKEY1";
+const FILE_MAGIC: &[u8] = b"DSVAULT1\n";
+const CHECK_VALUE: &[u8] = b"diary-sensei vault";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VaultStatus {
+    pub enabled: bool,
+    /// Enabled and the passphrase has not been entered this session.
+    pub locked: bool,
+}
+
+struct Unlocked {
+    root: PathBuf,
+    key: SecretKey,
+}
+
+static UNLOCKED: Mutex<Option<Unlocked>> = Mutex::new(None);
+
+fn locked_error() -> String {
+    "The diary is encrypted and locked. Enter your passphrase in Settings.".to_string()
+}
+
+/// The vault `path` belongs to, if any.
+fn vault_root(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|dir| dir.join(VAULT_FILE).is_file())
+}
+
+/// The key for `path` if it lies in the unlocked vault.
+fn key_for(path: &Path) -> Option<SecretKey> {
+    let guard = UNLOCKED.lock().ok()?;
+    guard.as_ref().filter(|u| path.starts_with(&u.root)).map(|u| u.key.clone())
+}
+
+fn seal_file(key: &SecretKey, data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut out = FILE_MAGIC.to_vec();
+    out.extend(crypto::seal(key, data)?);
+    Ok(out)
+}
+
+/// Contents of a file read from disk: decrypted when sealed, as-is when plain.
+fn open_file(key: Option<&SecretKey>, data: Vec<u8>) -> Result<Vec<u8>, String> {
+    let Some(sealed) = data.strip_prefix(FILE_MAGIC) else { return Ok(data) };
+    crypto::open(key.ok_or_else(locked_error)?, sealed)
+}
+
+fn create_key_file(root: &Path, passphrase: &str) -> Result<SecretKey, String> {
+    let salt = crypto::random_salt();
+    let key = SecretKey::derive(passphrase, &salt)?;
+    let mut data = VAULT_MAGIC.to_vec();
+    data.extend_from_slice(&salt);
+    data.extend(crypto::seal(&key, CHECK_VALUE)?);
+    fs::create_dir_all(root).map_err(|e| e.to_string())?;
+    atomic::write_file(&root.join(VAULT_FILE), &data, false)?;
+    Ok(key)
+}
+
+/// Derive the key from `passphrase`, failing if it is not the vault's passphrase.
+fn open_key_file(root: &Path, passphrase: &str) -> Result<SecretKey, String> {
+    let data = fs::read(root.join(VAULT_FILE)).map_err(|e| format!("Cannot read the vault key file: {}", e))?;
+    let rest = data
+        .strip_prefix(VAULT_MAGIC)
+        .filter(|rest| rest.len() > SALT_LEN)
+        .ok_or_else(|| "The vault key file is corrupted".to_string())?;
+    let (salt, sealed) = rest.split_at(SALT_LEN);
+    let key = SecretKey::derive(passphrase, salt)?;
+    if crypto::open(&key, sealed)? != CHECK_VALUE {
+        return Err("The vault key file is corrupted".to_string());
+    }
+    Ok(key)
+}
+
+/// Encrypt (or decrypt) every file under `root` that is not already in that form.
+/// Safe to run again after an interruption. Returns the number of files converted.
+fn convert_in(root: &Path, key: &SecretKey, encrypt: bool) -> Result<usize, String> {
+    let mut converted = 0;
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for item in fs::read_dir(&dir).map_err(|e| e.to_string())?.flatten() {
+            let path = item.path();
+            if path.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            if path == root.join(VAULT_FILE) {
+                continue;
+            }
+            let data = fs::read(&path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+            let sealed = data.starts_with(FILE_MAGIC);
+            if encrypt && !sealed {
+                atomic::write_file(&path, &seal_file(key, &data)?, false)?;
+            } else if !encrypt && sealed {
+                atomic::write_file(&path, &open_file(Some(key), data)?, false)?;
+            } else {
+                continue;
+            }
+            converted += 1;
+        }
+    }
+    Ok(converted)
+}
+
+// --- File access ---
+
+/// Read a file under `entries_dir`, decrypting it if needed.
+pub fn read(path: &Path) -> Result<Vec<u8>, String> {
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    open_file(key_for(path).as_ref(), data)
+}
+
+pub fn read_to_string(path: &Path) -> Result<String, String> {
+    String::from_utf8(read(path)?).map_err(|e| format!("{} is not valid UTF-8: {}", path.display(), e))
+}
+
+/// `atomic::write_file`, encrypting the data first when `path` is inside a vault.
+pub fn write(path: &Path, data: &[u8], backup: bool) -> Result<(), String> {
+    match key_for(path) {
+        Some(key) => atomic::write_file(path, &seal_file(&key, data)?, backup),
+        None if vault_root(path).is_some() => Err(locked_error()),
+        None => atomic::write_file(path, data, backup),
+    }
+}
+
+/// Fail when `path` is inside a locked vault, for listings that would otherwise
+/// skip every unreadable file and come back empty.
+pub fn ensure_unlocked(path: &Path) -> Result<(), String> {
+    if key_for(path).is_none() && vault_root(path).is_some() {
+        return Err(locked_error());
+    }
+    Ok(())
+}
+
+// --- Public API ---
+
+pub fn status() -> Result<VaultStatus, String> {
+    let root = storage::entries_dir()?;
+    let enabled = root.join(VAULT_FILE).is_file();
+    Ok(VaultStatus { enabled, locked: enabled && key_for(&root).is_none() })
+}
+
+fn set_unlocked(root: PathBuf, key: SecretKey) -> Result<(), String> {
+    *UNLOCKED.lock().map_err(|e| e.to_string())? = Some(Unlocked { root, key });
+    Ok(())
+}
+
+pub fn unlock(passphrase: &str) -> Result<(), String> {
+    let root = storage::entries_dir()?;
+    if !root.join(VAULT_FILE).is_file() {
+        return Err("Encryption is not turned on for this diary".to_string());
+    }
+    let key = open_key_file(&root, passphrase)?;
+    set_unlocked(root, key)
+}
+
+/// Forget the key, along with the search index loaded from the vault.
+pub fn lock() -> Result<(), String> {
+    *UNLOCKED.lock().map_err(|e| e.to_string())? = None;
+    search::unload();
+    Ok(())
+}
+
+/// Turn encryption on, encrypting every existing file. The first call chooses the
+/// passphrase; calling again with it finishes an interrupted migration. Returns
+/// the number of files encrypted.
+pub fn enable(passphrase: &str) -> Result<usize, String> {
+    if passphrase.is_empty() {
+        return Err("Passphrase cannot be empty".to_string());
+    }
+    let root = storage::entries_dir()?;
+    let key = if root.join(VAULT_FILE).is_file() {
+        open_key_file(&root, passphrase)?
+    } else {
+        create_key_file(&root, passphrase)?
+    };
+    // Unlock first so that saves made during the migration are encrypted too
+    set_unlocked(root.clone(), key.clone())?;
+    convert_in(&root, &key, true)
+}
+
+/// Decrypt every file and turn encryption off. Returns the number of files decrypted.
+pub fn disable(passphrase: &str) -> Result<usize, String> {
+    let root = storage::entries_dir()?;
+    let key = open_key_file(&root, passphrase)?;
+    let converted = convert_in(&root, &key, false)?;
+    // The key file goes last, so an interrupted run can be repeated
+    fs::remove_file(root.join(VAULT_FILE)).map_err(|e| e.to_string())?;
+    *UNLOCKED.lock().map_err(|e| e.to_string())? = None;
+    Ok(converted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::temp_dir;
+
+    fn setup(name: &str) -> PathBuf {
+        let root = temp_dir(&format!("vault-{}", name));
+        let month = root.join("2026").join("06");
+        fs::create_dir_all(month.join("2026-06-01_100000")).unwrap();
+        fs::create_dir_all(root.join(".history").join("2026-06-01_100000")).unwrap();
+        fs::write(month.join("2026-06-01_100000.md"), "---\ntitle: \"Secret\"\n---\n\nDear diary").unwrap();
+        fs::write(month.join("2026-06-01_100000").join("photo.png"), [0x89, b'P', b'N', b'G']).unwrap();
+        fs::write(root.join(".history").join("2026-06-01_100000").join("20260601-100000000.md"), "Dear diary").unwrap();
+        root
+    }
+
+    #[test]
+    fn test_convert_encrypts_and_decrypts_every_file() {
+        let root = setup("convert");
+        let entry = root.join("2026").join("06").join("2026-06-01_100000.md");
+        let key = create_key_file(&root, "passphrase").unwrap();
+        assert_eq!(vault_root(&entry), Some(root.as_path()));
+
+        assert_eq!(convert_in(&root, &key, true).unwrap(), 3);
+        // Already encrypted files are left alone, so a rerun is a no-op
+        assert_eq!(convert_in(&root, &key, true).unwrap(), 0);
+        let raw = fs::read(&entry).unwrap();
+        assert!(raw.starts_with(FILE_MAGIC));
+        assert!(!String::from_utf8_lossy(&raw).contains("diary"));
+        assert!(open_file(None, raw.clone()).is_err());
+        assert!(open_file(Some(&key), raw).unwrap().ends_with(b"Dear diary"));
+
+        assert_eq!(convert_in(&root, &key, false).unwrap(), 3);
+        assert!(fs::read_to_string(&entry).unwrap().ends_with("Dear diary"));
+        assert_eq!(fs::read(root.join("2026/06/2026-06-01_100000/photo.png")).unwrap(), [0x89, b'P', b'N', b'G']);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_key_file_checks_passphrase() {
+        let root = setup("passphrase");
+        create_key_file(&root, "passphrase").unwrap();
+        assert!(open_key_file(&root, "passphrase").is_ok());
+        assert!(open_key_file(&root, "Passphrase").is_err());
+
+        fs::write(root.join(VAULT_FILE), b"DSVAULTKEY1short").unwrap();
+        assert!(open_key_file(&root, "passphrase").is_err_and(|e| e.contains("corrupted")));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_write_refuses_locked_vault() {
+        let root = setup("locked");
+        let entry = root.join("2026").join("06").join("2026-06-01_100000.md");
+        write(&entry, b"plain", false).unwrap();
+        assert_eq!(read_to_string(&entry).unwrap(), "plain");
+        assert!(ensure_unlocked(&root).is_ok());
+
+        create_key_file(&root, "passphrase").unwrap();
+        assert_eq!(write(&entry, b"leak", false).unwrap_err(), locked_error());
+        assert!(ensure_unlocked(&root).is_err());
+        assert_eq!(fs::read_to_string(&entry).unwrap(), "plain");
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
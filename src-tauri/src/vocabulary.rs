@@ -0,0 +1,282 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::storage;
+
+const NOTEBOOK_FILE: &str = "vocabulary.json";
+
+/// Serializes read-modify-write cycles on the notebook file across commands.
+static NOTEBOOK_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VocabItem {
+    pub id: u64,
+    pub language: String,           // language code, e.g. "ja"
+    pub lemma: String,              // dictionary form
+    #[serde(default)]
+    pub reading: Option<String>,    // e.g. furigana or pinyin
+    pub meaning: String,
+    #[serde(default)]
+    pub example: Option<String>,
+    #[serde(default)]
+    pub source_entry_id: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub created_at: String,         // ISO 8601
+    pub updated_at: String,
+}
+
+/// A word to be added: a vocabulary candidate from `extract_vocabulary` or typed by the user.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewVocabItem {
+    pub language: String,
+    pub lemma: String,
+    #[serde(default)]
+    pub reading: Option<String>,
+    #[serde(default)]
+    pub meaning: String,
+    #[serde(default)]
+    pub example: Option<String>,
+    #[serde(default)]
+    pub source_entry_id: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Contents of `vocabulary.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Notebook {
+    #[serde(default)]
+    pub items: Vec<VocabItem>,
+}
+
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = tags
+        .iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    normalized.sort();
+    normalized.dedup();
+    normalized
+}
+
+fn now() -> String {
+    chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+impl Notebook {
+    /// Whether `lemma` is already saved for `language`, ignoring case.
+    pub fn contains(&self, language: &str, lemma: &str) -> bool {
+        let lemma = lemma.trim().to_lowercase();
+        self.items
+            .iter()
+            .any(|item| item.language == language && item.lemma.to_lowercase() == lemma)
+    }
+
+    /// Add new words, skipping blanks and lemmas already in the notebook for that
+    /// language. Returns the items actually added.
+    pub fn add(&mut self, new_items: Vec<NewVocabItem>, timestamp: &str) -> Vec<VocabItem> {
+        let mut next_id = self.items.iter().map(|item| item.id).max().unwrap_or(0) + 1;
+        let mut added = vec![];
+        for new in new_items {
+            let lemma = new.lemma.trim();
+            if lemma.is_empty() || self.contains(&new.language, lemma) {
+                continue;
+            }
+            let item = VocabItem {
+                id: next_id,
+                language: new.language,
+                lemma: lemma.to_string(),
+                reading: new.reading.filter(|r| !r.trim().is_empty()),
+                meaning: new.meaning.trim().to_string(),
+                example: new.example.filter(|e| !e.trim().is_empty()),
+                source_entry_id: new.source_entry_id,
+                tags: normalize_tags(&new.tags),
+                created_at: timestamp.to_string(),
+                updated_at: timestamp.to_string(),
+            };
+            next_id += 1;
+            self.items.push(item.clone());
+            added.push(item);
+        }
+        added
+    }
+
+    /// Items matching the optional language and tag filters, newest first.
+    pub fn list(&self, language: Option<&str>, tag: Option<&str>) -> Vec<VocabItem> {
+        let mut items: Vec<VocabItem> = self
+            .items
+            .iter()
+            .filter(|item| language.map_or(true, |l| item.language == l))
+            .filter(|item| tag.map_or(true, |t| item.tags.iter().any(|it| it == t)))
+            .cloned()
+            .collect();
+        items.sort_by_key(|item| std::cmp::Reverse(item.id));
+        items
+    }
+
+    /// Replace the editable fields of the item with `edited.id`.
+    pub fn update(&mut self, edited: VocabItem, timestamp: &str) -> Result<VocabItem, String> {
+        if edited.lemma.trim().is_empty() {
+            return Err("Word cannot be empty".to_string());
+        }
+        let item = self
+            .items
+            .iter_mut()
+            .find(|item| item.id == edited.id)
+            .ok_or_else(|| format!("Vocabulary item not found: {}", edited.id))?;
+        item.language = edited.language;
+        item.lemma = edited.lemma.trim().to_string();
+        item.reading = edited.reading.filter(|r| !r.trim().is_empty());
+        item.meaning = edited.meaning.trim().to_string();
+        item.example = edited.example.filter(|e| !e.trim().is_empty());
+        item.source_entry_id = edited.source_entry_id;
+        item.tags = normalize_tags(&edited.tags);
+        item.updated_at = timestamp.to_string();
+        Ok(item.clone())
+    }
+
+    /// Add and remove tags on several items at once. Returns the number of items changed.
+    pub fn tag(&mut self, ids: &[u64], add: &[String], remove: &[String], timestamp: &str) -> usize {
+        let add = normalize_tags(add);
+        let remove = normalize_tags(remove);
+        let mut changed = 0;
+        for item in self.items.iter_mut().filter(|item| ids.contains(&item.id)) {
+            let mut tags = item.tags.clone();
+            tags.extend(add.iter().cloned());
+            tags.retain(|t| !remove.contains(t));
+            let tags = normalize_tags(&tags);
+            if tags != item.tags {
+                item.tags = tags;
+                item.updated_at = timestamp.to_string();
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    /// Remove items by id. Returns the number removed.
+    pub fn delete(&mut self, ids: &[u64]) -> usize {
+        let before = self.items.len();
+        self.items.retain(|item| !ids.contains(&item.id));
+        before - self.items.len()
+    }
+}
+
+// --- File access ---
+
+fn notebook_path() -> Result<PathBuf, String> {
+    Ok(storage::entries_dir()?.join(NOTEBOOK_FILE))
+}
+
+fn load_from(path: &Path) -> Result<Notebook, String> {
+    storage::read_json_file(path, "Vocabulary notebook")
+}
+
+/// Read the notebook.
+pub fn read() -> Result<Notebook, String> {
+    let _guard = NOTEBOOK_LOCK.lock().map_err(|e| e.to_string())?;
+    load_from(&notebook_path()?)
+}
+
+/// Load, change and save the notebook as one step. `f` gets the current timestamp.
+pub fn modify<T>(f: impl FnOnce(&mut Notebook, &str) -> Result<T, String>) -> Result<T, String> {
+    let _guard = NOTEBOOK_LOCK.lock().map_err(|e| e.to_string())?;
+    let path = notebook_path()?;
+    let mut notebook = load_from(&path)?;
+    let result = f(&mut notebook, &now())?;
+    storage::write_json_file(&path, &notebook)?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::temp_dir;
+
+    const T0: &str = "2026-03-01T10:00:00";
+    const T1: &str = "2026-03-02T11:00:00";
+
+    fn new_item(language: &str, lemma: &str) -> NewVocabItem {
+        NewVocabItem {
+            language: language.to_string(),
+            lemma: lemma.to_string(),
+            reading: None,
+            meaning: "meaning".to_string(),
+            example: None,
+            source_entry_id: Some("2026-03-01_100000".to_string()),
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn test_add_assigns_ids_and_skips_duplicates() {
+        let mut notebook = Notebook::default();
+        let added = notebook.add(vec![new_item("ja", "食べる"), new_item("en", "went")], T0);
+        assert_eq!(added.iter().map(|i| i.id).collect::<Vec<_>>(), vec![1, 2]);
+
+        let added = notebook.add(vec![new_item("ja", "食べる"), new_item("en", "Went"), new_item("ja", " "), new_item("ja", "飲む")], T1);
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].id, 3);
+        assert_eq!(notebook.items.len(), 3);
+    }
+
+    #[test]
+    fn test_list_filters_by_language_and_tag() {
+        let mut notebook = Notebook::default();
+        notebook.add(vec![new_item("ja", "食べる"), new_item("ja", "飲む"), new_item("it", "andare")], T0);
+        notebook.tag(&[1, 3], &["verbs".to_string()], &[], T1);
+        assert_eq!(notebook.list(Some("ja"), None).len(), 2);
+        let verbs = notebook.list(None, Some("verbs"));
+        assert_eq!(verbs.iter().map(|i| i.id).collect::<Vec<_>>(), vec![3, 1]);
+        assert_eq!(notebook.list(Some("ja"), Some("verbs")).len(), 1);
+    }
+
+    #[test]
+    fn test_tag_adds_and_removes() {
+        let mut notebook = Notebook::default();
+        notebook.add(vec![new_item("ja", "食べる")], T0);
+        assert_eq!(notebook.tag(&[1], &[" N5 ".to_string(), "food".to_string()], &[], T1), 1);
+        assert_eq!(notebook.items[0].tags, vec!["N5", "food"]);
+        assert_eq!(notebook.items[0].updated_at, T1);
+        assert_eq!(notebook.tag(&[1], &[], &["food".to_string()], T1), 1);
+        assert_eq!(notebook.items[0].tags, vec!["N5"]);
+        assert_eq!(notebook.tag(&[1], &["N5".to_string()], &[], T1), 0);
+    }
+
+    #[test]
+    fn test_update_and_delete() {
+        let mut notebook = Notebook::default();
+        notebook.add(vec![new_item("ja", "食べる"), new_item("ja", "飲む")], T0);
+        let mut edited = notebook.items[0].clone();
+        edited.reading = Some("たべる".to_string());
+        edited.meaning = "to eat".to_string();
+        let updated = notebook.update(edited, T1).unwrap();
+        assert_eq!(updated.reading.as_deref(), Some("たべる"));
+        assert_eq!(updated.created_at, T0);
+
+        let mut missing = notebook.items[0].clone();
+        missing.id = 99;
+        assert!(notebook.update(missing, T1).is_err());
+
+        assert_eq!(notebook.delete(&[1, 99]), 1);
+        assert_eq!(notebook.items.len(), 1);
+    }
+
+    #[test]
+    fn test_notebook_file_round_trip() {
+        let dir = temp_dir("vocab");
+        let path = dir.join(NOTEBOOK_FILE);
+        assert!(load_from(&path).unwrap().items.is_empty());
+
+        let mut notebook = Notebook::default();
+        notebook.add(vec![new_item("ja", "食べる")], T0);
+        storage::write_json_file(&path, &notebook).unwrap();
+        assert_eq!(load_from(&path).unwrap().items, notebook.items);
+
+        std::fs::write(&path, "{ not json").unwrap();
+        assert!(load_from(&path).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,5 +1,5 @@
 import { writable } from 'svelte/store';
-import type { AppConfig, DiaryEntry, EntryListItem, AppMode } from './types';
+import type { AppConfig, DiaryEntry, EntryListItem, AppMode, SearchResult, AiError } from './types';
 
 export const currentDate = writable<string>(new Date().toISOString().slice(0, 10));
 export const currentEntryId = writable<string | null>(null);
@@ -11,7 +11,6 @@ export const appMode = writable<AppMode>('correction');
 export const isLoading = writable<boolean>(false);
 export const error = writable<string>('');
 export const config = writable<AppConfig>({
-  api_key: '',
   default_language: 'ja',
   theme: 'warm-light',
   languages: [
@@ -34,6 +33,10 @@ export const isProcessing = writable<boolean>(false);
 export const isDirty = writable<boolean>(false);
 export const explanation = writable<string | null>(null);
 export const searchQuery = writable<string>('');
-export const searchResults = writable<EntryListItem[] | null>(null);
+export const searchResults = writable<SearchResult[] | null>(null);
 export const closedEntryIds = writable<Set<string>>(new Set());
 export const correctionOriginal = writable<string>('');
+// Text streamed so far for the running AI request, by language
+export const streamingText = writable<Record<string, string>>({});
+// Languages whose translation failed in the last request
+export const translationErrors = writable<Record<string, AiError>>({});
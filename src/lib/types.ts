@@ -7,12 +7,16 @@ export interface EntryMeta {
   date_format?: string;
   created_at?: string;     // ISO 8601: "2026-02-24T14:30:52"
   updated_at?: string;     // ISO 8601: "2026-02-24T14:30:52"
+  correction_strength?: CorrectionStrength | null;
+  private?: boolean;       // `private: true` in frontmatter: never sent to a remote provider
 }
 
 export interface DiaryEntry {
   meta: EntryMeta;
   original: string;
   translations: Record<string, string>;  // lang_code -> result text
+  explanations: Record<string, string>;  // lang_code -> explanation of the saved correction
+  edits: Record<string, CorrectionEdit[]>;  // lang_code -> structured edits of the saved correction
 }
 
 export interface EntryListItem {
@@ -25,16 +29,73 @@ export interface EntryListItem {
   updated_at?: string;
 }
 
+export interface SnippetPart {
+  text: string;
+  highlight: boolean;
+}
+
+export interface Snippet {
+  section: string; // "title", "original", or a language code
+  parts: SnippetPart[];
+}
+
+export interface TrashItem {
+  id: string;
+  date: string;
+  title: string;
+  deleted_at: string;
+}
+
+export interface RevisionInfo {
+  revision: string;
+  saved_at: string;
+  title: string;
+  size: number;
+}
+
+export interface SectionDiff {
+  section: string; // "title", "original", or a language code
+  hunks: DiffHunk[];
+}
+
+export type SearchSort = 'relevance' | 'date';
+
+// Also typed into the query: from:2026-01-01 to:2026-03-31 mode:correction lang:it in:original sort:date
+export interface SearchFilters {
+  from?: string | null; // YYYY-MM-DD
+  to?: string | null;
+  mode?: AppMode | null;
+  language?: string | null;
+  section?: string | null; // "title", "original", or a language code
+  sort?: SearchSort | null;
+}
+
+export interface SearchResult extends EntryListItem {
+  score: number;
+  snippet: Snippet | null;
+}
+
+// Design decision D3: how much the AI intervenes when correcting
+export type CorrectionStrength = 'hint' | 'standard' | 'full';
+
 export interface LanguageConfig {
   code: string;
   name: string;
   date_format: string;
 }
 
-export type AiProvider = 'ollama' | 'claude';
+export type AiProvider = 'ollama' | 'claude' | 'openai';
+
+// Unset fields fall back to the global generation settings in AppConfig
+export interface TaskOverrides {
+  model?: string | null;
+  max_tokens?: number | null;
+  temperature?: number | null;
+  timeout_secs?: number | null;
+}
 
 export interface AppConfig {
-  api_key: string;
+  api_key?: string;         // write-only: saved to the secret store, never loaded
   default_language: string;
   theme: string;
   languages: LanguageConfig[];
@@ -43,12 +104,208 @@ export interface AppConfig {
   ai_provider: AiProvider;
   ollama_model: string;
   ollama_url: string;
+  openai_url: string;       // e.g. "http://localhost:8080/v1"
+  openai_model: string;
+  openai_api_key?: string;  // write-only, like api_key
+  claude_model: string;
+  max_tokens: number;
+  temperature: number | null;
+  timeout_secs: number;
+  correction_overrides: TaskOverrides;
+  translation_overrides: TaskOverrides;
+  correction_strength: CorrectionStrength;
+  history_retention: number; // revisions kept per entry; 0 = off
+  trash_retention_days: number; // 0 = keep until the trash is emptied
+  redact_pii: boolean; // replace personal details before text goes to a remote provider
+  redaction_names: string[];
+}
+
+export type SecretBackend = 'keychain' | 'encrypted_file';
+
+export interface SecretStatus {
+  backend: SecretBackend;
+  locked: boolean;       // encrypted_file only: passphrase not entered yet
+  initialized: boolean;  // encrypted_file only: false until a passphrase is chosen
+  claude_api_key: boolean;
+  openai_api_key: boolean;
+}
+
+export interface VaultStatus {
+  enabled: boolean;  // entries, images and data files are encrypted at rest
+  locked: boolean;   // enabled and the passphrase has not been entered yet
+}
+
+export interface AuditRecord {
+  sent_at: string;         // ISO 8601
+  entry_id: string | null;
+  task: 'correction' | 'translation' | 'vocabulary';
+  provider: AiProvider;
+  chars: number;           // diary characters sent, summed over requests
+}
+
+export interface ProviderCapabilities {
+  remote: boolean;
+  requires_api_key: boolean;
+  streaming: boolean;
+  structured_output: boolean;
+}
+
+export interface ProviderInfo {
+  id: AiProvider;
+  capabilities: ProviderCapabilities;
+}
+
+export type MistakeCategory =
+  | 'particles' | 'verb_conjugation' | 'articles' | 'tense'
+  | 'word_choice' | 'spelling' | 'punctuation' | 'other';
+
+// One change made by the AI (in hint mode: one problem found, replacement is empty)
+export interface CorrectionEdit {
+  original: string;
+  replacement: string;
+  category: MistakeCategory;
+  reason: string;
+}
+
+export interface CategoryCount {
+  category: MistakeCategory;
+  count: number;
+}
+
+export interface MonthlyMistakes {
+  month: string;  // "2026-02"
+  entries: number;
+  total: number;
+  categories: CategoryCount[];
+}
+
+// Returned by get_mistake_stats
+export interface MistakeStats {
+  languages: {
+    language: string;
+    entries: number;
+    total: number;
+    top_categories: CategoryCount[];  // most frequent first
+    monthly: MonthlyMistakes[];  // oldest first
+  }[];
+}
+
+// Aligned diff piece: joining every `original` gives the input, every `corrected` the output
+export interface DiffHunk {
+  kind: 'equal' | 'delete' | 'insert' | 'replace';
+  original: string;
+  corrected: string;
 }
 
 export interface CorrectionResult {
   corrected: string;
   explanation: string | null;
+  edits: CorrectionEdit[];
+  hunks: DiffHunk[];
+  strength: CorrectionStrength;
+}
+
+// Payload of the "ai-stream" event emitted while a request with a request_id runs
+export interface StreamChunk {
+  request_id: string;
+  language: string | null;  // target language for translate_text, null for corrections
+  delta: string;
+}
+
+export type AiErrorCode =
+  | 'not_configured' | 'auth' | 'rate_limited' | 'overloaded' | 'timeout'
+  | 'connection_refused' | 'model_not_found' | 'bad_request' | 'server_error'
+  | 'network' | 'malformed_output' | 'refused' | 'cancelled';
+
+// Per-language result of translate_text: exactly one of the two is set
+export interface TranslationOutcome {
+  text: string | null;
+  error: AiError | null;
+}
+
+// Rejection value of correct_text / translate_text
+export interface AiError {
+  code: AiErrorCode;
+  message: string;
+  retry_after_secs: number | null;
+}
+
+export interface OllamaModel {
+  name: string;
+  size: number;             // bytes on disk
+  modified_at: string | null;
+  details: {
+    family: string | null;
+    parameter_size: string | null;
+    quantization_level: string | null;
+  };
+}
+
+export interface OllamaStatus {
+  url: string;
+  reachable: boolean;
+  version: string | null;
+  models: OllamaModel[];
+  configured_model: string;
+  configured_model_installed: boolean;
+  error: AiError | null;
+}
+
+// Payload of the "ollama-pull-progress" event
+export interface PullProgressEvent {
+  request_id: string | null;
+  model: string;
+  status: string;
+  digest: string | null;
+  total: number | null;
+  completed: number | null;
 }
 
 export type AppMode = 'correction' | 'translation';
 export type ViewMode = 'edit' | 'read';
+
+// Vocabulary notebook (vocabulary.json beside the entries)
+export interface VocabItem {
+  id: number;
+  language: string;
+  lemma: string;
+  reading: string | null;
+  meaning: string;
+  example: string | null;
+  source_entry_id: string | null;
+  tags: string[];
+  created_at: string;
+  updated_at: string;
+}
+
+// Candidate from extract_vocabulary, or a word to pass to add_vocabulary
+export type NewVocabItem = Omit<VocabItem, 'id' | 'created_at' | 'updated_at'>;
+
+// Spaced-repetition reviews (reviews.json beside the entries)
+export type ReviewGrade = 'again' | 'hard' | 'good' | 'easy';
+
+export interface ReviewCard {
+  id: string;
+  kind: 'mistake' | 'vocabulary';
+  language: string;
+  front: string;  // what was written, or the word
+  back: string;  // the correction, or the meaning
+  note: string | null;  // reason for the correction, or the reading
+  context: string | null;
+  source_entry_id: string | null;
+  schedule: {
+    ease: number;
+    interval_days: number;
+    repetitions: number;
+    lapses: number;
+    due: string;  // "2026-03-01"
+    last_reviewed: string | null;
+  };
+}
+
+// Returned by export_anki: number of notes written
+export interface AnkiExportSummary {
+  corrections: number;
+  vocabulary: number;
+  skipped: string[];  // file names of entries that could not be read
+}